@@ -0,0 +1,220 @@
+//! Token Vault Rust 客户端辅助库
+//!
+//! 与链上程序共用金库名称的校验规则和 PDA 派生逻辑（直接复用 `token_vault` crate
+//! 中的实现），保证客户端算出的地址与程序 `initialize` 创建的金库一致，
+//! UI 也无法创建 "Vault" / "vault " 这类看起来相同的金库。
+//!
+//! ```
+//! use anchor_lang::prelude::Pubkey;
+//! use token_vault_client::vault_address;
+//!
+//! let authority = Pubkey::new_unique();
+//! let (from_input, _) = vault_address(&authority, " My-Vault ").unwrap();
+//! let (canonical, _) = vault_address(&authority, "my-vault").unwrap();
+//! assert_eq!(from_input, canonical);
+//!
+//! assert!(vault_address(&authority, "").is_err());
+//! assert!(vault_address(&authority, "my vault").is_err());
+//! ```
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solana_rpc_client_types::filter::{Memcmp, RpcFilterType};
+
+pub use token_vault::{
+    find_config_address, find_registry_address, find_vault_address, normalize_vault_name,
+    validate_vault_name, HistoryEntry, HistoryKind, Vault, VaultConfig, VaultError, VaultHistory,
+    VaultRegistry, ID, MAX_HISTORY_ENTRIES, MAX_REGISTRY_VAULTS, MAX_VAULT_NAME_LEN,
+    MAX_WITHDRAW_FEE_BPS,
+};
+
+/// 金库账户中 `authority` 字段的偏移（紧跟 8 字节判别器）
+pub const VAULT_AUTHORITY_OFFSET: usize = 8;
+
+/// 金库账户中 `original_authority` 字段的偏移
+pub const VAULT_ORIGINAL_AUTHORITY_OFFSET: usize = VAULT_AUTHORITY_OFFSET + 32;
+
+/// 由用户输入的名称计算金库 PDA
+///
+/// 先用 `normalize_vault_name` 转换为规范形式，再按程序的 seeds 派生地址。
+/// 返回的 bump 与链上 `vault.bump` 相同。
+pub fn vault_address(original_authority: &Pubkey, input: &str) -> Result<(Pubkey, u8), VaultError> {
+    let name = normalize_vault_name(input)?;
+    Ok(find_vault_address(original_authority, &name))
+}
+
+/// 计算金库操作历史账户的 PDA（`[b"history", vault]`）
+pub fn history_address(vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"history", vault.as_ref()], &ID)
+}
+
+/// 解码操作历史账户的数据（`getAccountInfo` 返回的 data）
+///
+/// 返回按时间顺序（从旧到新）排列的记录，最多 `MAX_HISTORY_ENTRIES` 条。
+///
+/// ```
+/// use anchor_lang::prelude::Pubkey;
+/// use anchor_lang::AccountSerialize;
+/// use token_vault_client::{decode_history, HistoryEntry, HistoryKind, VaultHistory};
+///
+/// let mut history = VaultHistory {
+///     vault: Pubkey::new_unique(),
+///     entries: Vec::new(),
+///     next: 0,
+///     total: 0,
+///     bump: 255,
+/// };
+/// for amount in 1..=70 {
+///     history.push(HistoryEntry {
+///         kind: HistoryKind::Deposit,
+///         actor: Pubkey::new_unique(),
+///         amount,
+///         timestamp: amount as i64,
+///     });
+/// }
+///
+/// let mut data = Vec::new();
+/// history.try_serialize(&mut data).unwrap();
+///
+/// // 写满 64 条后最旧的 6 条被覆盖
+/// let entries = decode_history(&data).unwrap();
+/// assert_eq!(entries.len(), 64);
+/// assert_eq!(entries.first().unwrap().amount, 7);
+/// assert_eq!(entries.last().unwrap().amount, 70);
+/// ```
+pub fn decode_history(data: &[u8]) -> anchor_lang::Result<Vec<HistoryEntry>> {
+    let history = VaultHistory::try_deserialize(&mut &data[..])?;
+    Ok(history.recent())
+}
+
+/// 解码金库登记表账户的数据，返回创建者登记的所有金库地址
+pub fn decode_registry(data: &[u8]) -> anchor_lang::Result<Vec<Pubkey>> {
+    let registry = VaultRegistry::try_deserialize(&mut &data[..])?;
+    Ok(registry.vaults)
+}
+
+/// `getProgramAccounts` 过滤器：当前所有者是 `authority` 的所有金库
+///
+/// 包含判别器过滤，不会匹配到其他类型的账户（例如 `owner` 同样位于偏移 8 的登记表）。
+/// 返回的类型与 `solana_client::rpc_filter::RpcFilterType` 相同。
+///
+/// ```
+/// use anchor_lang::prelude::Pubkey;
+/// use anchor_lang::AccountSerialize;
+/// use solana_rpc_client_types::filter::RpcFilterType;
+/// use token_vault::LegacyVault;
+/// use token_vault_client::{vaults_by_authority, vaults_by_original_authority, Vault};
+///
+/// let creator = Pubkey::new_unique();
+/// let owner = Pubkey::new_unique();
+/// let vault = Vault::from(LegacyVault {
+///     authority: owner,
+///     original_authority: creator,
+///     vault_name: "my-vault".to_string(),
+///     total_deposits: 0,
+///     total_withdrawals: 0,
+///     bump: 255,
+/// });
+/// let mut data = Vec::new();
+/// vault.try_serialize(&mut data).unwrap();
+///
+/// let matches = |filters: Vec<RpcFilterType>| {
+///     filters.iter().all(|filter| match filter {
+///         RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&data),
+///         _ => false,
+///     })
+/// };
+/// assert!(matches(vaults_by_authority(&owner)));
+/// assert!(!matches(vaults_by_authority(&creator)));
+/// assert!(matches(vaults_by_original_authority(&creator)));
+/// assert!(!matches(vaults_by_original_authority(&owner)));
+/// ```
+pub fn vaults_by_authority(authority: &Pubkey) -> Vec<RpcFilterType> {
+    vault_filters(VAULT_AUTHORITY_OFFSET, authority)
+}
+
+/// `getProgramAccounts` 过滤器：由 `original_authority` 创建的所有金库
+///
+/// 所有权转移后仍能按创建者找到金库（与登记表和金库 PDA 的派生方式一致）。
+pub fn vaults_by_original_authority(original_authority: &Pubkey) -> Vec<RpcFilterType> {
+    vault_filters(VAULT_ORIGINAL_AUTHORITY_OFFSET, original_authority)
+}
+
+/// 金库判别器 + 指定偏移处的公钥
+fn vault_filters(offset: usize, key: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, Vault::DISCRIMINATOR.to_vec())),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, key.to_bytes().to_vec())),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(name: &str) -> Option<VaultError> {
+        validate_vault_name(name).err()
+    }
+
+    /// 客户端规范化后的名称必须能通过链上的校验，并派生出相同的 PDA（seeds 使用名称的原始字节）
+    fn assert_matches_program(input: &str, canonical: &str) {
+        assert_eq!(normalize_vault_name(input).unwrap(), canonical);
+        assert!(validate_vault_name(canonical).is_ok());
+
+        let authority = Pubkey::new_from_array([7; 32]);
+        let expected = Pubkey::find_program_address(
+            &[b"vault", authority.as_ref(), canonical.as_bytes()],
+            &ID,
+        );
+        assert_eq!(vault_address(&authority, input).unwrap(), expected);
+    }
+
+    #[test]
+    fn uppercase_is_lowered() {
+        assert_matches_program("My-Vault_1", "my-vault_1");
+        assert_matches_program("VAULT", "vault");
+
+        // 链上只接受规范形式，大写名称不会被转换
+        assert!(matches!(rejected("My-Vault_1"), Some(VaultError::InvalidVaultName)));
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_trimmed() {
+        assert_matches_program("  my-vault\t\n", "my-vault");
+        assert!(matches!(rejected(" my-vault"), Some(VaultError::InvalidVaultName)));
+
+        // 中间的空白不会被去掉
+        assert!(matches!(normalize_vault_name("my vault"), Err(VaultError::InvalidVaultName)));
+    }
+
+    #[test]
+    fn non_ascii_is_rejected() {
+        for input in ["金库", "café", "CAFÉ", "vault\u{200b}", "ｖａｕｌｔ"] {
+            assert!(matches!(normalize_vault_name(input), Err(VaultError::InvalidVaultName)), "{input:?}");
+            assert!(matches!(rejected(input), Some(VaultError::InvalidVaultName)), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn empty_is_rejected() {
+        for input in ["", " ", "\t\n"] {
+            assert!(matches!(normalize_vault_name(input), Err(VaultError::InvalidVaultName)), "{input:?}");
+        }
+        assert!(matches!(rejected(""), Some(VaultError::InvalidVaultName)));
+    }
+
+    #[test]
+    fn length_limit_is_in_bytes() {
+        let max = "a".repeat(MAX_VAULT_NAME_LEN);
+        assert_matches_program(&max, &max);
+        assert_matches_program(&format!(" {} ", max.to_uppercase()), &max);
+
+        let too_long = "a".repeat(MAX_VAULT_NAME_LEN + 1);
+        assert!(matches!(normalize_vault_name(&too_long), Err(VaultError::NameTooLong)));
+        assert!(matches!(rejected(&too_long), Some(VaultError::NameTooLong)));
+
+        // 16 个 "é" 正好 32 字节：长度没有超，但不是 ASCII；17 个是 34 字节，先报超长
+        assert!(matches!(rejected(&"é".repeat(16)), Some(VaultError::InvalidVaultName)));
+        assert!(matches!(rejected(&"é".repeat(17)), Some(VaultError::NameTooLong)));
+    }
+}
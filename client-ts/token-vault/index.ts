@@ -67,6 +67,12 @@ async function main() {
     program.programId            // 程序 ID
   );
 
+  // 金库登记表 PDA：每个创建者一个，列出其创建的所有金库
+  const [registryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), authority.toBuffer()],
+    program.programId
+  );
+
   console.log("📋 金库配置:");
   console.log(`  名称: ${vaultName}`);
   console.log(`  PDA: ${vaultPda.toBase58()}`);
@@ -123,7 +129,7 @@ async function main() {
     // 调用 deposit 指令
     // anchor.BN 是大数类型，用于处理 u64
     const depositTx1 = await program.methods
-      .deposit(new anchor.BN(depositAmount1))  // 存款金额
+      .deposit(new anchor.BN(depositAmount1), null)  // 存款金额
       .accountsPartial({
         vault: vaultPda,                       // 金库账户
         depositor: authority,                  // 存款人（签名者）
@@ -148,7 +154,7 @@ async function main() {
     const depositAmount2 = 0.3 * LAMPORTS_PER_SOL;
 
     const depositTx2 = await program.methods
-      .deposit(new anchor.BN(depositAmount2))
+      .deposit(new anchor.BN(depositAmount2), null)
       .accountsPartial({
         vault: vaultPda,
         depositor: authority,
@@ -230,15 +236,25 @@ async function main() {
     const newAuthorityKeypair = Keypair.generate();
     const newAuthority = newAuthorityKeypair.publicKey;
 
-    // 第一次转移：从当前所有者转给新所有者
+    // 第一次转移：两步转移，先由当前所有者提议
     const transferTx = await program.methods
-      .transferAuthority(newAuthority)         // 新所有者地址
+      .proposeAuthority(newAuthority)          // 新所有者地址
       .accountsPartial({
         vault: vaultPda,                       // 金库账户
         authority: authority,                  // 当前所有者（必须签名）
       })
       .rpc();
 
+    // 再由新所有者签名接受（防止地址填错导致金库永久失控）
+    await program.methods
+      .acceptAuthority()
+      .accountsPartial({
+        vault: vaultPda,
+        newAuthority: newAuthority,            // 新所有者（必须签名）
+      })
+      .signers([newAuthorityKeypair])
+      .rpc();
+
     console.log(`✅ 所有权已转移`);
     console.log(`  交易: ${transferTx}`);
     console.log(`  新所有者: ${newAuthority.toBase58()}`);
@@ -249,15 +265,22 @@ async function main() {
     // ========================================================================
 
     // 第二次转移：从新所有者转回原所有者
-    // 注意：现在需要新所有者签名
-    const transferBackTx = await program.methods
-      .transferAuthority(authority)            // 转回原所有者
+    // 注意：提议需要新所有者签名，接受需要原所有者签名
+    await program.methods
+      .proposeAuthority(authority)             // 转回原所有者
       .accountsPartial({
         vault: vaultPda,                       // 金库 PDA（地址不变！）
         authority: newAuthority,               // 当前所有者（新所有者）
       })
       .signers([newAuthorityKeypair])          // 新所有者签名
       .rpc();
+    const transferBackTx = await program.methods
+      .acceptAuthority()
+      .accountsPartial({
+        vault: vaultPda,
+        newAuthority: authority,               // 原所有者接受
+      })
+      .rpc();
 
     console.log(`✅ 已转回原所有者`);
     console.log(`  交易: ${transferBackTx}`);
@@ -268,16 +291,18 @@ async function main() {
     // ========================================================================
     console.log("=== 7️⃣ 关闭金库 ===");
 
-    // 调用 close_vault 指令
+    // 调用 close_vault 指令（不设余额阈值，不指定 destination）
     // 这会：
     // 1. 将金库中所有 SOL（包括租金）转给所有者
     // 2. 清空账户数据
     // 3. 将账户标记为已关闭
     const closeTx = await program.methods
-      .closeVault()
+      .closeVault(null)
       .accountsPartial({
         vault: vaultPda,           // 要关闭的金库
         authority: authority,      // 所有者（必须签名，接收余额）
+        destination: null,         // 不指定时余额转给所有者
+        registry: registryPda,     // 从登记表中移除该金库
       })
       .rpc();
 
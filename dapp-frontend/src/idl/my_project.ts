@@ -113,6 +113,18 @@ export type MyProject = {
       ]
     }
   ],
+  "errors": [
+    {
+      "code": 6000,
+      "name": "overflow",
+      "msg": "Counter overflow"
+    },
+    {
+      "code": 6001,
+      "name": "underflow",
+      "msg": "Counter cannot go below zero"
+    }
+  ],
   "types": [
     {
       "name": "counter",
@@ -17,14 +17,14 @@ pub mod my_project {
 
     pub fn increment(ctx: Context<Update>) -> Result<()> {
         let counter = &mut ctx.accounts.counter;
-        counter.count = counter.count.checked_add(1).unwrap();
+        counter.count = counter.count.checked_add(1).ok_or(CounterError::Overflow)?;
         msg!("Counter incremented to: {}", counter.count);
         Ok(())
     }
 
     pub fn decrement(ctx: Context<Update>) -> Result<()> {
         let counter = &mut ctx.accounts.counter;
-        counter.count = counter.count.checked_sub(1).unwrap();
+        counter.count = counter.count.checked_sub(1).ok_or(CounterError::Underflow)?;
         msg!("Counter decremented to: {}", counter.count);
         Ok(())
     }
@@ -56,3 +56,11 @@ pub struct Counter {
     pub count: u64,
     pub authority: Pubkey,
 }
+
+#[error_code]
+pub enum CounterError {
+    #[msg("Counter overflow")]
+    Overflow,
+    #[msg("Counter cannot go below zero")]
+    Underflow,
+}
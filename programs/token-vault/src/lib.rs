@@ -3,15 +3,38 @@
 // ============================================================================
 //
 // 功能：
-// - 创建金库账户
-// - 存入 SOL
-// - 提取 SOL
+// - 创建金库账户（所有者可以是其他程序的 PDA，通过 CPI 调用）
+// - 金库登记表（每个创建者一个，列出其创建的所有金库）
+// - 存入 SOL（可带外部参考号，用于链下对账）
+// - 提取 SOL（指定金额、全部可用余额或按比例），可按全局配置收取手续费
+// - 存入 / 提取 SPL 代币（每个 mint 一个金库代币账户）
+// - 时间锁（到期解锁或线性释放）
+// - M-of-N 多签（提款、转移所有权、关闭金库通过提案执行）
+// - 两步转移所有权（提议 → 新所有者接受，可取消）
+// - 存款回执（记录每个存款人的存款），可退款模式下存款人可取回自己的存款
+// - 提款限额（每个时间窗口最多可提取的 SOL）
+// - 存款限制（最低存款金额、余额上限）
+// - 委托提款（授权其他密钥在额度和有效期内提款）
+// - 接收者白名单（增删接收者需等待延迟期）
+// - 紧急暂停（守护者冻结，所有者和守护者共同解冻）
+// - 继承（所有者长期不活跃后，受益人可以接管金库）
+// - 支付流（按秒累积，收款人随时领取）
+// - 账户版本与迁移（旧版 125 字节金库通过 migrate_vault 扩容）
+// - 原生质押（闲置 SOL 委托给验证者，区分质押中和可用余额）
+// - 安全关闭（有关联账户时拒绝，可设置余额阈值和接收地址）
+// - 链上操作历史（环形缓冲区保存最近 64 条存款、提款和所有权转移）
+// - Anchor 事件（所有状态变化都可以通过 IDL 解码）
 // - 查询余额
 // - 权限控制
 //
 // ============================================================================
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use solana_stake_interface::instruction as stake_instruction;
+use solana_stake_interface::{program as stake_program, stake_history};
+use solana_stake_interface::state::{Authorized, Lockup, StakeStateV2};
 
 // 声明程序 ID（部署时由 Anchor 自动生成）
 declare_id!("FukTyMfW3YnifZmVD66Y26nXECk68HNbpQ4DfifU16wZ");
@@ -26,47 +49,70 @@ pub mod token_vault {
     /// - 创建一个新的金库账户
     /// - 设置金库所有者和原始创建者
     /// - 初始化统计数据（存款、提款记录）
+    /// - 把金库登记到所有者的登记表（首次创建金库时同时创建登记表）
     ///
     /// # 参数
-    /// - `vault_name`: 金库名称（最多 32 字符，用于 PDA 计算和标识）
+    /// - `vault_name`: 金库名称（规范形式，见 `validate_vault_name`；用于 PDA 计算和标识）
     ///
     /// # 权限
     /// - 任何人都可以调用，但创建的金库属于调用者
     ///
+    /// # 重要说明
+    /// - 只接受规范形式的名称，避免 "Vault"、"vault " 等看起来相同的名称派生出不同的金库
+    /// - 客户端应先用 `normalize_vault_name` 处理用户输入
+    ///
     /// # 返回
     /// - `Ok(())`: 成功
     /// - `Err(VaultError::NameTooLong)`: 名称超过 32 字符
+    /// - `Err(VaultError::InvalidVaultName)`: 名称为空或不是规范形式
+    /// - `Err(VaultError::RegistryFull)`: 登记表已满
     pub fn initialize(ctx: Context<Initialize>, vault_name: String) -> Result<()> {
-        // 验证：金库名称不能超过 32 字符
-        require!(vault_name.len() <= 32, VaultError::NameTooLong);
-
-        // 获取金库账户的可变引用
-        let vault = &mut ctx.accounts.vault;
-
-        // 设置当前所有者（可以通过 transfer_authority 转移）
-        vault.authority = ctx.accounts.authority.key();
-
-        // 设置原始创建者（永不改变，用于 PDA seeds 计算）
-        vault.original_authority = ctx.accounts.authority.key();
-
-        // 设置金库名称
-        vault.vault_name = vault_name.clone();
-
-        // 初始化统计数据：总存款金额
-        vault.total_deposits = 0;
+        init_vault(&mut ctx.accounts.vault, ctx.accounts.authority.key(), vault_name, ctx.bumps.vault)?;
 
-        // 初始化统计数据：总提款金额
-        vault.total_withdrawals = 0;
-
-        // 保存 PDA bump seed（用于后续签名）
-        vault.bump = ctx.bumps.vault;
+        register_vault(
+            &ctx.accounts.registry,
+            ctx.accounts.authority.key(),
+            ctx.accounts.vault.key(),
+            ctx.bumps.registry,
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )
+    }
 
-        // 记录日志：金库创建成功
-        msg!("✅ Vault '{}' initialized", vault_name);
-        msg!("   Authority: {}", vault.authority);
-        msg!("   Vault PDA: {}", ctx.accounts.vault.key());
+    /// 由单独的付款人创建金库
+    ///
+    /// # 功能
+    /// - 与 `initialize` 相同，但租金由 `payer` 支付，`authority` 不需要持有 SOL
+    /// - 主要用于 CPI：其他程序可以把自己的 PDA 作为金库所有者，
+    ///   通过 `invoke_signed` 为 PDA 签名（见 `programs/vault-caller`）
+    ///
+    /// # 参数
+    /// - `vault_name`: 金库名称（规范形式，见 `validate_vault_name`）
+    ///
+    /// # 权限
+    /// - `authority` 和 `payer` 都必须签名
+    ///
+    /// # 重要说明
+    /// - 金库 PDA 仍由 `authority` 派生：`[b"vault", authority, vault_name]`
+    /// - 金库登记到 `authority` 的登记表，登记表的租金和扩容费用也由 `payer` 支付
+    /// - 之后的所有者操作（提款、关闭等）同样由调用程序用 `invoke_signed` 签名
+    ///
+    /// # 返回
+    /// - `Ok(())`: 成功
+    /// - `Err(VaultError::NameTooLong)`: 名称超过 32 字符
+    /// - `Err(VaultError::InvalidVaultName)`: 名称为空或不是规范形式
+    /// - `Err(VaultError::RegistryFull)`: 登记表已满
+    pub fn initialize_with_payer(ctx: Context<InitializeWithPayer>, vault_name: String) -> Result<()> {
+        init_vault(&mut ctx.accounts.vault, ctx.accounts.authority.key(), vault_name, ctx.bumps.vault)?;
 
-        Ok(())
+        register_vault(
+            &ctx.accounts.registry,
+            ctx.accounts.authority.key(),
+            ctx.accounts.vault.key(),
+            ctx.bumps.registry,
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )
     }
 
     /// 存款
@@ -74,24 +120,55 @@ pub mod token_vault {
     /// # 功能
     /// - 将 SOL 从存款人账户转入金库
     /// - 更新金库的总存款统计
+    /// - 传入存款回执时更新回执（首次存款时自动创建，由存款人支付租金）
+    /// - 传入历史账户时写入操作历史
     ///
     /// # 参数
     /// - `amount`: 存款金额（单位：lamports，1 SOL = 10^9 lamports）
+    /// - `reference`: 可选的外部参考号（如订单号、发票号），写入事件和日志，供链下系统对账
     ///
     /// # 权限
     /// - 任何人都可以向金库存款（不仅限于所有者）
+    /// - 所有者通过 `set_require_reference` 开启后，每笔存款都必须带参考号
+    /// - 金库开启可退款模式后，每笔存款都必须传入存款回执（退款以回执为准）
     ///
     /// # 安全性
     /// - 使用 checked_add 防止数值溢出
+    /// - 设置了存款限制时，金额不能低于 `min_deposit`，存款后的余额不能超过 `max_balance`
     ///
     /// # 返回
     /// - `Ok(())`: 存款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
     /// - `Err(VaultError::InvalidAmount)`: 金额必须大于 0
+    /// - `Err(VaultError::InvalidReference)`: 参考号全为 0
+    /// - `Err(VaultError::ReferenceRequired)`: 金库要求参考号但没有传入
+    /// - `Err(VaultError::ReceiptRequired)`: 金库开启了可退款模式但没有传入存款回执
+    /// - `Err(VaultError::DepositTooSmall)`: 金额低于最低存款金额
+    /// - `Err(VaultError::MaxBalanceExceeded)`: 存款后余额超过上限
     /// - `Err(VaultError::Overflow)`: 累计存款金额溢出
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    /// - `Err(VaultError::HistoryAccountRequired)`: 传入的历史账户不属于该金库
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, reference: Option<[u8; 32]>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "deposit")?;
+
         // 验证：存款金额必须大于 0
         require!(amount > 0, VaultError::InvalidAmount);
 
+        // 验证：参考号（全 0 视为无效，避免和“没有参考号”混淆）
+        match reference {
+            Some(reference) => require!(reference != [0u8; 32], VaultError::InvalidReference),
+            None => require!(!ctx.accounts.vault.require_reference, VaultError::ReferenceRequired),
+        }
+
+        // 验证：可退款模式下必须记录存款回执，否则存款人无法退款
+        require!(
+            !ctx.accounts.vault.refundable || ctx.accounts.receipt.is_some(),
+            VaultError::ReceiptRequired
+        );
+
+        // 验证：存款限制（最低存款金额和余额上限）
+        ensure_deposit_within_limits(&ctx.accounts.vault, amount)?;
+
         // 通过 CPI (跨程序调用) 将 SOL 从存款人转到金库
         // CPI 是 Solana 程序之间调用的标准方式
         let cpi_context = CpiContext::new(
@@ -111,12 +188,58 @@ pub mod token_vault {
         vault.total_deposits = vault.total_deposits.checked_add(amount)
             .ok_or(VaultError::Overflow)?;
 
-        // 获取金库当前余额（用于日志记录）
+        // 更新存款回执（首次存款时初始化字段）
+        if let (Some(receipt), Some(bump)) = (ctx.accounts.receipt.as_mut(), ctx.bumps.receipt) {
+            if receipt.vault == Pubkey::default() {
+                receipt.vault = vault.key();
+                receipt.depositor = ctx.accounts.depositor.key();
+                receipt.bump = bump;
+            }
+            receipt.total_deposited = receipt.total_deposited.checked_add(amount)
+                .ok_or(VaultError::Overflow)?;
+
+            // 版本 3 之前的金库没有该字段，迁移前的回执不计入
+            if vault.has_fields(VAULT_VERSION_LINKED_ACCOUNTS) {
+                vault.receipt_balance = vault.receipt_balance.checked_add(amount)
+                    .ok_or(VaultError::Overflow)?;
+            }
+        }
+
+        // 写入操作历史（如果开启了）
+        // 存款人不一定知道金库开启了操作历史（第三方存款、按旧账户列表构造的 CPI），
+        // 所以不传历史账户时跳过，不拒绝存款
+        if ctx.accounts.history.is_some() {
+            record_history(
+                vault,
+                ctx.accounts.history.as_deref_mut(),
+                HistoryKind::Deposit,
+                ctx.accounts.depositor.key(),
+                amount,
+            )?;
+        }
+
+        // 获取金库当前余额（用于事件和日志）
         let vault_balance = vault.get_lamports();
 
+        emit!(Deposited {
+            vault: vault.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            total_deposits: vault.total_deposits,
+            balance: vault_balance,
+            reference,
+            slot: Clock::get()?.slot,
+        });
+
         // 记录日志
         msg!("💰 Deposited {} lamports", amount);
+        if let Some(reference) = reference {
+            msg!("   Reference: {}", reference_hex(&reference));
+        }
         msg!("   Total deposits: {}", vault.total_deposits);
+        if let Some(receipt) = &ctx.accounts.receipt {
+            msg!("   Depositor balance: {}", receipt.balance());
+        }
         msg!("   Vault balance: {}", vault_balance);
 
         Ok(())
@@ -137,60 +260,103 @@ pub mod token_vault {
     ///
     /// # 安全性
     /// - 自动计算并保留租金豁免最低余额，防止账户被清除
+    /// - 设置了时间锁时，只能提取已解锁的部分
+    /// - 设置了提款限额时，每个时间窗口内的累计提款不能超过上限
+    /// - 开启了接收者白名单时，接收者必须在白名单中且已生效
     /// - 使用 checked_add 防止数值溢出
     ///
+    /// # 手续费
+    /// - 全局配置（`init_config`）设置了 `withdraw_fee_bps` 时，从 `amount` 中扣除手续费转给协议金库，
+    ///   接收者收到 `amount - fee`
+    /// - 时间锁、提款限额和余额都按 `amount`（含手续费）检查
+    ///
     /// # 返回
     /// - `Ok(())`: 提款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::ReceiverNotAllowed)`: 接收者不在白名单中
     /// - `Err(VaultError::InvalidAmount)`: 金额必须大于 0
+    /// - `Err(VaultError::StillLocked)`: 金额超过当前已解锁的部分
+    /// - `Err(VaultError::RateLimitExceeded)`: 超过当前时间窗口的提款限额
     /// - `Err(VaultError::InsufficientFunds)`: 可用余额不足
     /// - `Err(VaultError::Overflow)`: 累计提款金额溢出
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    /// - `Err(VaultError::TreasuryMismatch)`: 需要收取手续费但没有传入配置中的协议金库
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-        // 验证：提款金额必须大于 0
-        require!(amount > 0, VaultError::InvalidAmount);
-
-        // 获取金库当前余额
-        let vault_balance = ctx.accounts.vault.get_lamports();
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "withdraw")?;
 
-        // 计算租金豁免所需的最低余额
-        // 在 Solana 中，账户必须保留足够的余额才能免除租金
-        let rent = Rent::get()?;
-        let min_balance = rent.minimum_balance(ctx.accounts.vault.to_account_info().data_len());
+        // 执行提款（多签、白名单、金额、时间锁、租金豁免检查都在辅助函数中完成）
+        process_withdraw(ctx.accounts, amount)?;
 
-        // 计算可用余额 = 总余额 - 最低保留余额
-        // saturating_sub 确保不会下溢（结果最小为 0）
-        let available_balance = vault_balance.saturating_sub(min_balance);
+        Ok(())
+    }
 
-        // 验证：可用余额必须大于等于提款金额
-        require!(available_balance >= amount, VaultError::InsufficientFunds);
+    /// 提取全部可用余额
+    ///
+    /// # 功能
+    /// - 提取金库当前的全部可用余额（总余额 − 租金豁免最低余额）
+    /// - 客户端不需要先查询余额、计算租金，也不会因为并发存款导致金额不准
+    /// - 接收者实际收到的金额通过返回值（`set_return_data`）返回，CPI 调用方可以直接读取
+    ///
+    /// # 权限
+    /// - **只有金库所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 与 `withdraw` 使用相同的检查，时间锁或提款限额不允许提取全部余额时会失败
+    /// - 与 `withdraw` 一样收取手续费：从金库提取全部可用余额，接收者收到扣除手续费后的金额
+    ///
+    /// # 返回
+    /// - `Ok(amount)`: 接收者实际收到的金额（lamports，不含手续费，与 `Withdrawn` 事件的 `amount` 相同）
+    /// - `Err(VaultError::InvalidAmount)`: 没有可用余额
+    /// - 以及 `withdraw` 的所有错误
+    pub fn withdraw_all(ctx: Context<Withdraw>) -> Result<u64> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "withdraw_all")?;
 
-        // 直接修改账户的 lamports（底层操作）
-        // 从金库减少 amount
-        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= amount;
-        // 给接收者增加 amount
-        **ctx.accounts.receiver.to_account_info().try_borrow_mut_lamports()? += amount;
+        let amount = available_balance(&ctx.accounts.vault)?;
+        process_withdraw(ctx.accounts, amount)
+    }
 
-        // 更新金库的总提款统计
-        let vault = &mut ctx.accounts.vault;
+    /// 按比例提款
+    ///
+    /// # 功能
+    /// - 提取当前可用余额的 `bps / 10000`（向下取整）
+    /// - 接收者实际收到的金额通过返回值（`set_return_data`）返回，CPI 调用方可以直接读取
+    /// - 与 `withdraw` 一样收取手续费（从按比例计算的金额中扣除）
+    ///
+    /// # 参数
+    /// - `bps`: 提款比例（基点，1 - 10000，10000 表示全部可用余额）
+    ///
+    /// # 权限
+    /// - **只有金库所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(amount)`: 接收者实际收到的金额（lamports，不含手续费，与 `Withdrawn` 事件的 `amount` 相同）
+    /// - `Err(VaultError::InvalidBps)`: 比例不在 1 - 10000 之间
+    /// - `Err(VaultError::InvalidAmount)`: 按比例计算的金额为 0
+    /// - 以及 `withdraw` 的所有错误
+    pub fn withdraw_bps(ctx: Context<Withdraw>, bps: u16) -> Result<u64> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "withdraw_bps")?;
 
-        // 使用 checked_add 安全地累加，防止溢出
-        vault.total_withdrawals = vault.total_withdrawals.checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
+        // 验证：比例必须在 1 - 10000 之间
+        require!(bps > 0 && bps <= MAX_BPS, VaultError::InvalidBps);
 
-        // 获取提款后的剩余余额（用于日志）
-        let remaining_balance = vault.get_lamports();
+        // 使用 u128 计算，避免乘法溢出
+        let available = available_balance(&ctx.accounts.vault)?;
+        let amount = (available as u128 * bps as u128 / MAX_BPS as u128) as u64;
+        let received = process_withdraw(ctx.accounts, amount)?;
 
-        // 记录日志
-        msg!("💸 Withdrew {} lamports", amount);
-        msg!("   Total withdrawals: {}", vault.total_withdrawals);
-        msg!("   Remaining balance: {}", remaining_balance);
+        msg!("   Share: {} bps of {}", bps, available);
 
-        Ok(())
+        Ok(received)
     }
 
-    /// 转移金库所有权
+    /// 一步转移金库所有权
     ///
     /// # 功能
-    /// - 将金库的控制权转移给新的所有者
+    /// - 直接将金库的控制权转移给新的所有者
     /// - 新所有者将拥有提款和再次转移的权限
     ///
     /// # 参数
@@ -198,22 +364,56 @@ pub mod token_vault {
     ///
     /// # 权限
     /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 金库必须通过 `set_one_step_transfer(true)` 显式开启
     ///
     /// # 重要说明
+    /// - 新所有者不需要签名，地址填错会永久失去金库控制权
+    /// - 推荐使用 `propose_authority` + `accept_authority` 两步转移
     /// - `authority` 字段会改变（当前所有者）
     /// - `original_authority` 字段**不会**改变（用于 PDA seeds）
     /// - PDA 地址始终不变，因为使用 original_authority 计算
     ///
     /// # 返回
     /// - `Ok(())`: 转移成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::OneStepTransferDisabled)`: 未开启一步转移
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
     pub fn transfer_authority(ctx: Context<TransferAuthority>, new_authority: Pubkey) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "transfer_authority")?;
+
         let vault = &mut ctx.accounts.vault;
 
+        // 验证：多签模式下必须通过提案转移
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：必须显式开启一步转移
+        require!(vault.one_step_transfer, VaultError::OneStepTransferDisabled);
+
         // 保存旧所有者（用于日志）
         let old_authority = vault.authority;
 
-        // 更新为新所有者
+        // 更新为新所有者，并清除未完成的两步转移和原所有者指定的受益人
         vault.authority = new_authority;
+        vault.pending_authority = None;
+        reset_inheritance(vault)?;
+
+        // 写入操作历史（actor 为新所有者）
+        record_history(
+            vault,
+            ctx.accounts.history.as_deref_mut(),
+            HistoryKind::AuthorityTransfer,
+            new_authority,
+            0,
+        )?;
+
+        emit!(AuthorityTransferred {
+            vault: vault.key(),
+            old_authority,
+            new_authority,
+            slot: Clock::get()?.slot,
+        });
 
         // 记录日志
         msg!("🔑 Authority transferred");
@@ -223,266 +423,6372 @@ pub mod token_vault {
         Ok(())
     }
 
-    /// 关闭金库并取回所有 SOL
+    /// 开启或关闭一步转移
     ///
     /// # 功能
-    /// - 关闭金库账户
-    /// - 将金库中所有剩余的 SOL 转给所有者
-    /// - 释放账户占用的空间
+    /// - 控制 `transfer_authority` 是否可用（默认关闭）
+    ///
+    /// # 参数
+    /// - `enabled`: true 开启，false 关闭
     ///
     /// # 权限
     /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
     ///
-    /// # 重要说明
-    /// - 关闭后金库账户将不复存在
-    /// - 所有余额（包括租金）都会转给所有者
-    /// - 此操作不可逆
-    ///
     /// # 返回
-    /// - `Ok(())`: 关闭成功
-    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
-        let vault = &ctx.accounts.vault;
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn set_one_step_transfer(ctx: Context<TransferAuthority>, enabled: bool) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_one_step_transfer")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
 
-        // 记录日志：显示金库最终统计
-        msg!("🔒 Closing vault '{}'", vault.vault_name);
-        msg!("   Total deposits: {}", vault.total_deposits);
-        msg!("   Total withdrawals: {}", vault.total_withdrawals);
-        msg!("   Final balance will be transferred to authority");
+        let vault = &mut ctx.accounts.vault;
+
+        vault.one_step_transfer = enabled;
 
-        // Anchor 会自动执行以下操作（通过 #[account(close = authority)] 约束）：
-        // 1. 将金库账户的所有 lamports 转给 authority
-        // 2. 清空账户数据
-        // 3. 将账户所有者设置为 System Program
+        msg!("⚙️  One-step authority transfer {}", if enabled { "enabled" } else { "disabled" });
 
         Ok(())
     }
-}
 
-// ============================================================================
-// 账户验证结构
-// ============================================================================
-// 以下结构定义了每个指令需要的账户及其约束条件
-// Anchor 会在运行时自动验证这些约束
+    /// 提议转移金库所有权（两步转移第一步）
+    ///
+    /// # 功能
+    /// - 记录待接受的新所有者，所有权暂不改变
+    /// - 新所有者调用 `accept_authority` 后才生效
+    /// - 重复调用会覆盖之前的提议
+    ///
+    /// # 参数
+    /// - `new_authority`: 新的所有者公钥
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 提议成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    pub fn propose_authority(ctx: Context<TransferAuthority>, new_authority: Pubkey) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "propose_authority")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
 
-/// 初始化金库指令的账户验证
-///
-/// # 账户说明
-/// - `vault`: 要创建的金库 PDA 账户
-/// - `authority`: 金库所有者（签名者，支付创建费用）
-/// - `system_program`: Solana 系统程序（用于创建账户）
-#[derive(Accounts)]
-#[instruction(vault_name: String)]  // 声明指令参数，用于约束中
-pub struct Initialize<'info> {
-    /// 金库账户（PDA）
-    #[account(
-        init,                           // 初始化新账户
-        payer = authority,              // 由 authority 支付账户创建费用
-        space = 8 + Vault::INIT_SPACE,  // 账户空间：8 字节判别器 + 数据大小
-        seeds = [                       // PDA seeds（用于派生地址）
-            b"vault",                   // 固定前缀
-            authority.key().as_ref(),   // 所有者公钥
-            vault_name.as_bytes()       // 金库名称
-        ],
-        bump                            // PDA bump seed（Anchor 自动查找）
-    )]
-    pub vault: Account<'info, Vault>,
+        let vault = &mut ctx.accounts.vault;
 
-    /// 金库所有者（必须签名，支付租金）
-    #[account(mut)]  // mut: 因为要扣除创建账户的费用
-    pub authority: Signer<'info>,
+        // 验证：多签模式下必须通过提案转移
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
 
-    /// Solana 系统程序（用于创建账户）
-    pub system_program: Program<'info, System>,
-}
+        vault.pending_authority = Some(new_authority);
 
-/// 存款指令的账户验证
-///
-/// # 账户说明
-/// - `vault`: 金库账户（接收存款）
-/// - `depositor`: 存款人（签名者，支付存款）
-/// - `system_program`: 系统程序（用于转账）
-///
-/// # 权限
-/// - 任何人都可以向金库存款，不需要是所有者
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    /// 金库账户（PDA，可变因为余额会增加）
-    #[account(
-        mut,                            // 可变：余额和统计数据会更新
-        seeds = [                       // 验证 PDA
-            b"vault",
-            vault.original_authority.as_ref(),  // 使用原始创建者（不变）
-            vault.vault_name.as_bytes()
-        ],
-        bump = vault.bump               // 使用存储的 bump
-    )]
-    pub vault: Account<'info, Vault>,
+        msg!("🔑 Authority transfer proposed");
+        msg!("   Current: {}", vault.authority);
+        msg!("   Pending: {}", new_authority);
 
-    /// 存款人（必须签名）
-    #[account(mut)]  // mut: 因为要扣除存款金额
-    pub depositor: Signer<'info>,
+        Ok(())
+    }
 
-    /// 系统程序（用于 SOL 转账）
-    pub system_program: Program<'info, System>,
-}
+    /// 接受金库所有权（两步转移第二步）
+    ///
+    /// # 功能
+    /// - 待接受的新所有者签名确认，`authority` 更新为新所有者
+    ///
+    /// # 权限
+    /// - 只有 `pending_authority` 本人可以调用（必须签名）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 转移成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::NotPendingAuthority)`: 没有待接受的转移，或签名者不是待接受的新所有者
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "accept_authority")?;
 
-/// 提款指令的账户验证
-///
-/// # 账户说明
-/// - `vault`: 金库账户（发送提款）
-/// - `authority`: 金库所有者（必须签名）
-/// - `receiver`: 接收者（可以是任何账户）
-/// - `system_program`: 系统程序
-///
-/// # 权限
-/// - **只有所有者**可以提款（通过 has_one 约束）
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    /// 金库账户（PDA，可变因为余额会减少）
-    #[account(
-        mut,                            // 可变：余额和统计数据会更新
-        has_one = authority,            // 验证：vault.authority 必须等于 authority 账户
-        seeds = [                       // 验证 PDA
-            b"vault",
-            vault.original_authority.as_ref(),  // 使用原始创建者
-            vault.vault_name.as_bytes()
-        ],
-        bump = vault.bump               // 使用存储的 bump
-    )]
-    pub vault: Account<'info, Vault>,
+        let vault = &mut ctx.accounts.vault;
 
-    /// 金库所有者（必须签名才能提款）
-    pub authority: Signer<'info>,
+        // 验证：多签模式下必须通过提案转移
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
 
-    /// 接收者（可以是任何账户，不需要签名）
-    /// CHECK: 此账户可以是任何地址，由所有者指定
-    #[account(mut)]  // mut: 因为会增加余额
-    pub receiver: AccountInfo<'info>,
+        // 验证：签名者必须是待接受的新所有者
+        let new_authority = ctx.accounts.new_authority.key();
+        require!(
+            vault.pending_authority == Some(new_authority),
+            VaultError::NotPendingAuthority
+        );
 
-    /// 系统程序（实际未使用，但保留以保持一致性）
-    pub system_program: Program<'info, System>,
-}
+        let old_authority = vault.authority;
+        vault.authority = new_authority;
+        vault.pending_authority = None;
+        reset_inheritance(vault)?;
 
-/// 转移所有权指令的账户验证
-///
-/// # 账户说明
-/// - `vault`: 金库账户
-/// - `authority`: 当前所有者（必须签名）
-///
-/// # 权限
-/// - **只有当前所有者**可以转移所有权
-#[derive(Accounts)]
-pub struct TransferAuthority<'info> {
-    /// 金库账户（PDA，可变因为 authority 会改变）
-    #[account(
-        mut,                            // 可变：authority 字段会更新
-        has_one = authority,            // 验证：必须是当前所有者
-        seeds = [                       // 验证 PDA
-            b"vault",
-            vault.original_authority.as_ref(),  // 使用原始创建者（永不改变）
-            vault.vault_name.as_bytes()
-        ],
-        bump = vault.bump               // 使用存储的 bump
-    )]
-    pub vault: Account<'info, Vault>,
+        // 写入操作历史（actor 为新所有者）
+        record_history(
+            vault,
+            ctx.accounts.history.as_deref_mut(),
+            HistoryKind::AuthorityTransfer,
+            new_authority,
+            0,
+        )?;
 
-    /// 当前所有者（必须签名）
-    pub authority: Signer<'info>,
-}
+        emit!(AuthorityTransferred {
+            vault: vault.key(),
+            old_authority,
+            new_authority,
+            slot: Clock::get()?.slot,
+        });
 
-/// 关闭金库指令的账户验证
-///
-/// # 账户说明
-/// - `vault`: 金库账户（将被关闭）
-/// - `authority`: 金库所有者（接收剩余余额）
-///
-/// # 权限
-/// - **只有当前所有者**可以关闭金库
-///
-/// # 效果
-/// - 金库账户被删除
-/// - 所有余额转给所有者
-#[derive(Accounts)]
-pub struct CloseVault<'info> {
-    /// 金库账户（PDA，将被关闭）
-    #[account(
-        mut,                            // 可变：账户会被关闭
-        has_one = authority,            // 验证：必须是当前所有者
-        close = authority,              // 关闭账户，余额转给 authority
-        seeds = [                       // 验证 PDA
-            b"vault",
-            vault.original_authority.as_ref(),  // 使用原始创建者
-            vault.vault_name.as_bytes()
-        ],
-        bump = vault.bump               // 使用存储的 bump
-    )]
-    pub vault: Account<'info, Vault>,
+        msg!("🔑 Authority transferred");
+        msg!("   From: {}", old_authority);
+        msg!("   To: {}", new_authority);
 
-    /// 金库所有者（必须签名，接收剩余余额）
-    #[account(mut)]  // mut: 因为会接收余额
-    pub authority: Signer<'info>,
-}
+        Ok(())
+    }
+
+    /// 取消待接受的所有权转移
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 取消成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotPendingAuthority)`: 没有待接受的转移
+    pub fn cancel_authority_transfer(ctx: Context<TransferAuthority>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "cancel_authority_transfer")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：必须有待接受的转移
+        let pending = vault.pending_authority.take()
+            .ok_or(VaultError::NotPendingAuthority)?;
+
+        msg!("❎ Authority transfer to {} cancelled", pending);
+
+        Ok(())
+    }
+
+    /// 开启可退款模式
+    ///
+    /// # 功能
+    /// - 开启后，存款人可以通过 `refund` 取回自己尚未被提走的存款
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 单向开关：开启后不能关闭，存款人可以放心依赖
+    ///
+    /// # 返回
+    /// - `Ok(())`: 开启成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn enable_refunds(ctx: Context<UpdateVaultSettings>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "enable_refunds")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        vault.refundable = true;
+
+        msg!("↩️  Refunds enabled for vault '{}'", vault.vault_name);
+
+        Ok(())
+    }
+
+    /// 设置存款是否必须带参考号
+    ///
+    /// # 功能
+    /// - 开启后，`deposit` 不带 `reference` 时会失败，保证每笔存款都能和链下订单对账
+    ///
+    /// # 参数
+    /// - `required`: 是否要求参考号
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MigrationRequired)`: 旧版金库需要先迁移
+    pub fn set_require_reference(ctx: Context<UpdateVaultSettings>, required: bool) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_require_reference")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        vault.require_reference = required;
+
+        msg!("🧾 Deposit reference required: {}", required);
+
+        Ok(())
+    }
+
+    /// 设置存款限制
+    ///
+    /// # 功能
+    /// - `min_deposit`: 单笔存款的最低金额（0 表示不限制），用于拦截粉尘存款
+    /// - `max_balance`: 金库余额上限（None 表示不限制），达到上限后拒绝新的存款
+    ///
+    /// # 参数
+    /// - `min_deposit`: 最低存款金额（lamports）
+    /// - `max_balance`: 余额上限（lamports，不包括租金，包括质押中的本金）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 只影响之后的存款，已经超过上限的余额不会被退还
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::InvalidAmount)`: 余额上限小于最低存款金额
+    /// - `Err(VaultError::MigrationRequired)`: 旧版金库需要先迁移
+    pub fn set_deposit_limits(
+        ctx: Context<UpdateVaultSettings>,
+        min_deposit: u64,
+        max_balance: Option<u64>,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_deposit_limits")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：上限不能小于最低存款金额（否则任何存款都会被拒绝）
+        if let Some(max_balance) = max_balance {
+            require!(max_balance >= min_deposit, VaultError::InvalidAmount);
+        }
+
+        let vault = &mut ctx.accounts.vault;
+
+        vault.min_deposit = min_deposit;
+        vault.max_balance = max_balance;
+
+        msg!("🚧 Deposit limits updated");
+        msg!("   Min deposit: {}", min_deposit);
+        msg!("   Max balance: {:?}", max_balance);
+
+        Ok(())
+    }
+
+    /// 设置继承人（死手开关）
+    ///
+    /// # 功能
+    /// - 指定受益人和不活跃期限：所有者超过 `inactivity_period` 秒没有任何操作后，
+    ///   受益人可以通过 `claim_inheritance` 成为新的所有者
+    /// - 传入 None 取消继承设置
+    ///
+    /// # 参数
+    /// - `beneficiary`: 受益人（None 表示取消）
+    /// - `inactivity_period`: 不活跃期限（秒，必须大于 0；取消时忽略）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 所有者的任何操作（提款、修改设置等）和 `heartbeat` 都会刷新 `last_heartbeat`
+    /// - 所有权转移后继承设置会被清除，需要由新所有者重新设置
+    /// - 多签模式下不能设置（领取继承会绕过多签转移所有权）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 已启用多签
+    /// - `Err(VaultError::InvalidInactivityPeriod)`: 不活跃期限不大于 0
+    /// - `Err(VaultError::MigrationRequired)`: 旧版金库需要先迁移
+    pub fn set_beneficiary(
+        ctx: Context<UpdateVaultSettings>,
+        beneficiary: Option<Pubkey>,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_beneficiary")?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：多签模式下所有权变更必须通过提案执行
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        match beneficiary {
+            Some(beneficiary) => {
+                // 验证：不活跃期限必须大于 0
+                require!(inactivity_period > 0, VaultError::InvalidInactivityPeriod);
+
+                vault.beneficiary = Some(beneficiary);
+                vault.inactivity_period = inactivity_period;
+
+                msg!("🪦 Beneficiary set: {}", beneficiary);
+                msg!("   Inactivity period: {} seconds", inactivity_period);
+            }
+            None => {
+                vault.beneficiary = None;
+                vault.inactivity_period = 0;
+
+                msg!("🪦 Beneficiary removed");
+            }
+        }
+
+        // 设置本身也是所有者操作，从现在开始计时
+        vault.last_heartbeat = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// 心跳
+    ///
+    /// # 功能
+    /// - 刷新 `last_heartbeat`，表明所有者仍然活跃（不做其他修改）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MigrationRequired)`: 旧版金库需要先迁移
+    pub fn heartbeat(ctx: Context<UpdateVaultSettings>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "heartbeat")?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：版本 7 之前的金库没有该字段，需要先迁移
+        require!(vault.has_fields(VAULT_VERSION_INHERITANCE), VaultError::MigrationRequired);
+
+        vault.last_heartbeat = Clock::get()?.unix_timestamp;
+
+        msg!("💓 Heartbeat at {}", vault.last_heartbeat);
+
+        Ok(())
+    }
+
+    /// 受益人领取金库
+    ///
+    /// # 功能
+    /// - 所有者超过不活跃期限没有操作后，受益人成为新的所有者
+    /// - 清除继承设置和未完成的两步转移
+    ///
+    /// # 权限
+    /// - 只有 `beneficiary` 本人可以调用（必须签名）
+    ///
+    /// # 重要说明
+    /// - 金库地址不变（PDA 由 original_authority 派生）
+    /// - 多签、时间锁等设置保持不变，受益人仍受这些限制
+    ///
+    /// # 返回
+    /// - `Ok(())`: 领取成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotBeneficiary)`: 签名者不是受益人
+    /// - `Err(VaultError::OwnerStillActive)`: 还没有超过不活跃期限
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    pub fn claim_inheritance(ctx: Context<ClaimInheritance>) -> Result<()> {
+        // 验证：金库未被暂停（守护者可以暂停金库阻止可疑的领取）
+        ensure_not_paused(&ctx.accounts.vault, "claim_inheritance")?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：签名者必须是受益人
+        let beneficiary = ctx.accounts.beneficiary.key();
+        require!(vault.beneficiary == Some(beneficiary), VaultError::NotBeneficiary);
+
+        // 验证：所有者已经超过不活跃期限
+        let now = Clock::get()?.unix_timestamp;
+        let deadline = vault.last_heartbeat
+            .checked_add(vault.inactivity_period)
+            .ok_or(VaultError::Overflow)?;
+        require!(now >= deadline, VaultError::OwnerStillActive);
+
+        let old_authority = vault.authority;
+        vault.authority = beneficiary;
+        vault.pending_authority = None;
+        reset_inheritance(vault)?;
+
+        // 写入操作历史（actor 为新所有者）
+        record_history(
+            vault,
+            ctx.accounts.history.as_deref_mut(),
+            HistoryKind::AuthorityTransfer,
+            beneficiary,
+            0,
+        )?;
+
+        emit!(AuthorityTransferred {
+            vault: vault.key(),
+            old_authority,
+            new_authority: beneficiary,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("🪦 Inheritance claimed");
+        msg!("   From: {}", old_authority);
+        msg!("   To: {}", beneficiary);
+
+        Ok(())
+    }
+
+    /// 存款人取回存款
+    ///
+    /// # 功能
+    /// - 从金库退还 SOL 给存款人，最多为其回执中的剩余存款
+    /// - 更新回执的已退款金额和金库的总提款统计
+    ///
+    /// # 参数
+    /// - `amount`: 退款金额（单位：lamports）
+    ///
+    /// # 权限
+    /// - 只有回执对应的存款人可以调用（必须签名）
+    /// - 金库必须已开启可退款模式
+    ///
+    /// # 重要说明
+    /// - 不受时间锁限制（时间锁只约束所有者）
+    /// - 所有者先提走资金时，退款受金库可用余额限制
+    ///
+    /// # 返回
+    /// - `Ok(())`: 退款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::RefundsDisabled)`: 金库未开启可退款模式
+    /// - `Err(VaultError::InvalidAmount)`: 金额必须大于 0
+    /// - `Err(VaultError::RefundExceedsDeposits)`: 超过存款人剩余存款
+    /// - `Err(VaultError::InsufficientFunds)`: 金库可用余额不足
+    pub fn refund(ctx: Context<Refund>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "refund")?;
+
+        // 验证：金库必须开启可退款模式
+        require!(ctx.accounts.vault.refundable, VaultError::RefundsDisabled);
+
+        // 验证：退款金额必须大于 0
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        // 验证：不能超过存款人剩余存款
+        require!(
+            amount <= ctx.accounts.receipt.balance(),
+            VaultError::RefundExceedsDeposits
+        );
+
+        let depositor = ctx.accounts.depositor.to_account_info();
+        transfer_from_vault(&mut ctx.accounts.vault, &depositor, amount)?;
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.total_refunded = receipt.total_refunded.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        // 迁移前的存款没有计入 receipt_balance，用 saturating_sub 避免下溢
+        let vault = &mut ctx.accounts.vault;
+        if vault.has_fields(VAULT_VERSION_LINKED_ACCOUNTS) {
+            vault.receipt_balance = vault.receipt_balance.saturating_sub(amount);
+        }
+
+        emit_withdrawn(&ctx.accounts.vault, depositor.key(), depositor.key(), amount, 0)?;
+
+        msg!("↩️  Refunded {} lamports to {}", amount, receipt.depositor);
+        msg!("   Depositor balance: {}", receipt.balance());
+        msg!("   Total withdrawals: {}", ctx.accounts.vault.total_withdrawals);
+
+        Ok(())
+    }
+
+    /// 设置提款限额
+    ///
+    /// # 功能
+    /// - 限制每个时间窗口（例如 86400 秒 = 1 天）内最多可提取的 lamports
+    /// - 对 `withdraw` 和多签提款生效
+    ///
+    /// # 参数
+    /// - `max_per_window`: 每个窗口最多可提取的金额（lamports）
+    /// - `window_seconds`: 窗口长度（秒）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 首次设置或收紧（上限不升高且窗口不缩短）立即生效
+    /// - 放宽需要等待一个当前窗口长度后才生效，
+    ///   防止所有者私钥泄露后被立即解除限额
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功（或已排队等待生效）
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::InvalidRateLimit)`: 上限或窗口长度为 0
+    pub fn set_rate_limit(
+        ctx: Context<UpdateVaultSettings>,
+        max_per_window: u64,
+        window_seconds: i64,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_rate_limit")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：上限和窗口长度必须大于 0
+        require!(max_per_window > 0 && window_seconds > 0, VaultError::InvalidRateLimit);
+
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+
+        match vault.rate_limit.as_mut() {
+            // 首次设置：立即生效
+            None => {
+                vault.rate_limit = Some(RateLimit {
+                    max_per_window,
+                    window_seconds,
+                    window_start: now,
+                    spent_in_window: 0,
+                    pending: None,
+                });
+                msg!("🚦 Rate limit set: {} lamports per {}s", max_per_window, window_seconds);
+            }
+            // 收紧：立即生效，并取消排队中的放宽
+            Some(limit) if max_per_window <= limit.max_per_window
+                && window_seconds >= limit.window_seconds =>
+            {
+                limit.max_per_window = max_per_window;
+                limit.window_seconds = window_seconds;
+                limit.pending = None;
+                msg!("🚦 Rate limit tightened: {} lamports per {}s", max_per_window, window_seconds);
+            }
+            // 放宽：等待一个当前窗口长度后生效
+            Some(limit) => {
+                let effective_ts = now.checked_add(limit.window_seconds)
+                    .ok_or(VaultError::Overflow)?;
+                limit.pending = Some(RateLimitChange {
+                    max_per_window,
+                    window_seconds,
+                    effective_ts,
+                });
+                msg!("🚦 Rate limit change queued: {} lamports per {}s", max_per_window, window_seconds);
+                msg!("   Effective at: {}", effective_ts);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 开启操作历史
+    ///
+    /// # 功能
+    /// - 创建历史账户（PDA: `[b"history", vault]`），保存最近 64 条操作
+    /// - 开启后 `deposit`、`withdraw`、`transfer_authority`、`accept_authority` 必须传入历史账户
+    /// - 客户端通过一次 `getAccountInfo` 即可读取最近的操作，不需要索引服务
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 开启后不能关闭，历史账户在 `close_vault` 时一起关闭
+    ///
+    /// # 返回
+    /// - `Ok(())`: 开启成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn enable_history(ctx: Context<EnableHistory>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "enable_history")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let history = &mut ctx.accounts.history;
+        history.vault = ctx.accounts.vault.key();
+        history.entries = Vec::new();
+        history.next = 0;
+        history.total = 0;
+        history.bump = ctx.bumps.history;
+
+        ctx.accounts.vault.history_enabled = true;
+
+        msg!("📜 Vault history enabled");
+        msg!("   Capacity: {} entries", MAX_HISTORY_ENTRIES);
+
+        Ok(())
+    }
+
+    /// 开启接收者白名单
+    ///
+    /// # 功能
+    /// - 创建白名单账户（PDA: `[b"allowlist", vault]`）
+    /// - 开启后 `withdraw` 和 `delegate_withdraw` 只能转给白名单中的接收者
+    ///
+    /// # 参数
+    /// - `delay_seconds`: 增删接收者的生效延迟（秒）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 开启后不能关闭，延迟也不能修改
+    /// - 延迟期给所有者留出在私钥泄露后转移资金的时间
+    ///
+    /// # 返回
+    /// - `Ok(())`: 开启成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::InvalidAllowlistDelay)`: 延迟必须大于 0
+    pub fn enable_allowlist(ctx: Context<EnableAllowlist>, delay_seconds: i64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "enable_allowlist")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：延迟必须大于 0
+        require!(delay_seconds > 0, VaultError::InvalidAllowlistDelay);
+
+        let allowlist = &mut ctx.accounts.allowlist;
+        allowlist.vault = ctx.accounts.vault.key();
+        allowlist.delay_seconds = delay_seconds;
+        allowlist.receivers = Vec::new();
+        allowlist.bump = ctx.bumps.allowlist;
+
+        ctx.accounts.vault.receiver_allowlist = true;
+
+        msg!("📋 Receiver allowlist enabled");
+        msg!("   Delay: {}s", delay_seconds);
+
+        Ok(())
+    }
+
+    /// 添加白名单接收者
+    ///
+    /// # 参数
+    /// - `receiver`: 接收者地址，在 `delay_seconds` 之后生效
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 添加成功（等待生效）
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::ReceiverAlreadyListed)`: 接收者已在白名单中（包括待移除的）
+    /// - `Err(VaultError::AllowlistFull)`: 白名单已满
+    pub fn add_receiver(ctx: Context<ManageAllowlist>, receiver: Pubkey) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "add_receiver")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let allowlist = &mut ctx.accounts.allowlist;
+
+        // 清理已到期移除的接收者
+        allowlist.prune(now);
+
+        // 验证：不能重复添加
+        require!(
+            !allowlist.receivers.iter().any(|r| r.receiver == receiver),
+            VaultError::ReceiverAlreadyListed
+        );
+
+        // 验证：白名单未满
+        require!(
+            allowlist.receivers.len() < MAX_ALLOWED_RECEIVERS,
+            VaultError::AllowlistFull
+        );
+
+        let active_ts = now.checked_add(allowlist.delay_seconds)
+            .ok_or(VaultError::Overflow)?;
+        allowlist.receivers.push(AllowedReceiver {
+            receiver,
+            active_ts,
+            removal_ts: None,
+        });
+
+        msg!("📋 Receiver {} added", receiver);
+        msg!("   Active at: {}", active_ts);
+
+        Ok(())
+    }
+
+    /// 移除白名单接收者
+    ///
+    /// # 参数
+    /// - `receiver`: 接收者地址
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 尚未生效的接收者立即移除（相当于取消添加）
+    /// - 已生效的接收者在 `delay_seconds` 之后移除，期间仍可接收提款
+    ///
+    /// # 返回
+    /// - `Ok(())`: 移除成功（或已排队等待生效）
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::ReceiverNotListed)`: 接收者不在白名单中，或移除已经在排队
+    pub fn remove_receiver(ctx: Context<ManageAllowlist>, receiver: Pubkey) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "remove_receiver")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let allowlist = &mut ctx.accounts.allowlist;
+
+        // 清理已到期移除的接收者
+        allowlist.prune(now);
+
+        let index = allowlist.receivers.iter()
+            .position(|r| r.receiver == receiver)
+            .ok_or(VaultError::ReceiverNotListed)?;
+
+        let delay_seconds = allowlist.delay_seconds;
+        let entry = &mut allowlist.receivers[index];
+
+        // 验证：已经排队移除的接收者不能再次移除（避免调用者把空操作当成新的移除）
+        require!(entry.removal_ts.is_none(), VaultError::ReceiverNotListed);
+
+        if now < entry.active_ts {
+            // 尚未生效：立即移除
+            allowlist.receivers.remove(index);
+            msg!("📋 Pending receiver {} removed", receiver);
+        } else {
+            // 已生效：等待延迟后移除
+            let removal_ts = now.checked_add(delay_seconds)
+                .ok_or(VaultError::Overflow)?;
+            entry.removal_ts = Some(removal_ts);
+            msg!("📋 Receiver {} removal queued", receiver);
+            msg!("   Removed at: {}", removal_ts);
+        }
+
+        Ok(())
+    }
+
+    /// 授权委托人
+    ///
+    /// # 功能
+    /// - 创建委托账户，允许 `delegate` 在额度内调用 `delegate_withdraw` 提款
+    /// - 适用于机器人等不应持有主密钥的场景
+    ///
+    /// # 参数
+    /// - `allowance`: 可提取的总额度（lamports）
+    /// - `expires_at`: 可选的过期时间（Unix 时间戳，秒），None 表示永不过期
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 授权成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::InvalidAmount)`: 额度必须大于 0
+    /// - `Err(VaultError::InvalidExpiry)`: 过期时间早于当前时间
+    pub fn grant_delegate(
+        ctx: Context<GrantDelegate>,
+        allowance: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "grant_delegate")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：多签模式下不能由单个密钥授权
+        require!(ctx.accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：额度必须大于 0
+        require!(allowance > 0, VaultError::InvalidAmount);
+
+        // 验证：过期时间必须在未来
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > Clock::get()?.unix_timestamp, VaultError::InvalidExpiry);
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        vault.open_delegates = vault.open_delegates.checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        let delegate_account = &mut ctx.accounts.delegate_account;
+        delegate_account.vault = ctx.accounts.vault.key();
+        delegate_account.delegate = ctx.accounts.delegate.key();
+        delegate_account.allowance = allowance;
+        delegate_account.expires_at = expires_at;
+        delegate_account.total_withdrawn = 0;
+        delegate_account.bump = ctx.bumps.delegate_account;
+
+        msg!("🤝 Delegate {} granted", delegate_account.delegate);
+        msg!("   Allowance: {}", allowance);
+        msg!("   Expires at: {:?}", expires_at);
+
+        Ok(())
+    }
+
+    /// 增加委托额度
+    ///
+    /// # 参数
+    /// - `amount`: 增加的额度（lamports）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 增加成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::InvalidAmount)`: 金额必须大于 0
+    /// - `Err(VaultError::Overflow)`: 额度溢出
+    pub fn top_up_delegate(ctx: Context<ManageDelegate>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "top_up_delegate")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：多签模式下不能由单个密钥增加额度
+        require!(ctx.accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：金额必须大于 0
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let delegate_account = &mut ctx.accounts.delegate_account;
+        delegate_account.allowance = delegate_account.allowance.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        msg!("🤝 Delegate {} topped up by {}", delegate_account.delegate, amount);
+        msg!("   Allowance: {}", delegate_account.allowance);
+
+        Ok(())
+    }
+
+    /// 撤销委托
+    ///
+    /// # 功能
+    /// - 关闭委托账户，租金退还给所有者
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 多签模式下也可以撤销（撤销只会减少风险）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 撤销成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "revoke_delegate")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.open_delegates = vault.open_delegates.saturating_sub(1);
+
+        let delegate_account = &ctx.accounts.delegate_account;
+
+        msg!("🚫 Delegate {} revoked", delegate_account.delegate);
+        msg!("   Unused allowance: {}", delegate_account.allowance);
+        msg!("   Total withdrawn: {}", delegate_account.total_withdrawn);
+
+        // Anchor 通过 close = authority 约束自动关闭委托账户
+
+        Ok(())
+    }
+
+    /// 委托人提款
+    ///
+    /// # 功能
+    /// - 委托人在剩余额度内从金库提取 SOL
+    /// - 与 `withdraw` 使用相同的时间锁、提款限额和租金豁免检查，同样收取手续费（额度按含手续费的金额扣除）
+    ///
+    /// # 参数
+    /// - `amount`: 提款金额（单位：lamports）
+    ///
+    /// # 权限
+    /// - 只有委托账户中记录的委托人可以调用（通过 has_one = delegate 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 提款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::DelegateExpired)`: 委托已过期
+    /// - `Err(VaultError::AllowanceExceeded)`: 超过剩余额度
+    /// - 以及 `withdraw` 的所有错误（包括接收者白名单）
+    pub fn delegate_withdraw(ctx: Context<DelegateWithdraw>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "delegate_withdraw")?;
+
+        // 验证：多签模式下委托失效
+        require!(ctx.accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：委托未过期
+        if let Some(expires_at) = ctx.accounts.delegate_account.expires_at {
+            require!(Clock::get()?.unix_timestamp < expires_at, VaultError::DelegateExpired);
+        }
+
+        // 验证：不能超过剩余额度
+        require!(
+            amount <= ctx.accounts.delegate_account.allowance,
+            VaultError::AllowanceExceeded
+        );
+
+        // 验证：接收者白名单（如果开启了）
+        let receiver = ctx.accounts.receiver.to_account_info();
+        ensure_receiver_allowed(&ctx.accounts.vault, ctx.accounts.allowlist.as_deref(), receiver.key)?;
+
+        let fee = withdraw_lamports(
+            &mut ctx.accounts.vault,
+            &receiver,
+            amount,
+            &ctx.accounts.config,
+            ctx.accounts.treasury.as_deref(),
+        )?;
+        let received = amount - fee.amount;
+
+        emit_withdrawn(&ctx.accounts.vault, ctx.accounts.delegate.key(), receiver.key(), received, fee.amount)?;
+
+        // 额度按含手续费的金额扣除
+        let delegate_account = &mut ctx.accounts.delegate_account;
+        delegate_account.allowance -= amount;
+        delegate_account.total_withdrawn = delegate_account.total_withdrawn.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        msg!("🤖 Delegate {} withdrew {} lamports", delegate_account.delegate, received);
+        log_fee(&fee);
+        msg!("   Receiver: {}", receiver.key());
+        msg!("   Remaining allowance: {}", delegate_account.allowance);
+        msg!("   Delegate total withdrawn: {}", delegate_account.total_withdrawn);
+
+        Ok(())
+    }
+
+    /// 创建支付流
+    ///
+    /// # 功能
+    /// - 从 `start_ts` 到 `end_ts` 每秒向 `recipient` 累积 `rate_per_second` lamports
+    /// - 收款人通过 `claim_stream` 随时领取已累积的部分
+    ///
+    /// # 参数
+    /// - `stream_id`: 支付流编号，必须等于 `vault.stream_count`（PDA seeds 的一部分）
+    /// - `recipient`: 收款人
+    /// - `rate_per_second`: 每秒累积的金额（lamports）
+    /// - `start_ts`: 开始时间（Unix 时间戳，秒）
+    /// - `end_ts`: 结束时间（Unix 时间戳，秒），必须晚于开始时间和当前时间
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 创建成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::InvalidStream)`: 编号、速率或时间参数不合法
+    pub fn create_stream(
+        ctx: Context<CreateStream>,
+        stream_id: u64,
+        recipient: Pubkey,
+        rate_per_second: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "create_stream")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：多签模式下不能由单个密钥创建
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：编号连续、速率大于 0、时间区间合法且尚未结束
+        require!(stream_id == vault.stream_count, VaultError::InvalidStream);
+        require!(rate_per_second > 0, VaultError::InvalidStream);
+        require!(start_ts < end_ts && now < end_ts, VaultError::InvalidStream);
+
+        vault.stream_count = vault.stream_count.checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+        vault.open_streams = vault.open_streams.checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        let stream = &mut ctx.accounts.stream;
+        stream.vault = vault.key();
+        stream.recipient = recipient;
+        stream.id = stream_id;
+        stream.rate_per_second = rate_per_second;
+        stream.start_ts = start_ts;
+        stream.end_ts = end_ts;
+        stream.claimed = 0;
+        stream.bump = ctx.bumps.stream;
+
+        // 验证：总金额不能溢出 u64
+        let total = stream.accrued(end_ts).ok_or(VaultError::InvalidStream)?;
+
+        msg!("🌊 Stream #{} created", stream_id);
+        msg!("   Recipient: {}", recipient);
+        msg!("   Rate: {} lamports/s from {} to {}", rate_per_second, start_ts, end_ts);
+        msg!("   Total: {}", total);
+
+        Ok(())
+    }
+
+    /// 领取支付流
+    ///
+    /// # 功能
+    /// - 将已累积但尚未领取的金额转给收款人
+    /// - 与 `withdraw` 使用相同的接收者白名单、时间锁、提款限额和租金豁免检查，同样收取手续费
+    ///
+    /// # 权限
+    /// - **只有收款人**可以调用（通过 has_one = recipient 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 领取成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NothingToClaim)`: 没有可领取的金额
+    /// - 以及 `withdraw` 的所有错误
+    pub fn claim_stream(ctx: Context<ClaimStream>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "claim_stream")?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = ctx.accounts.stream.claimable(now)?;
+
+        // 验证：必须有可领取的金额
+        require!(amount > 0, VaultError::NothingToClaim);
+
+        let recipient = ctx.accounts.recipient.to_account_info();
+        let fee = settle_stream(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.stream,
+            ctx.accounts.allowlist.as_deref(),
+            &recipient,
+            amount,
+            &ctx.accounts.config,
+            ctx.accounts.treasury.as_deref(),
+        )?;
+
+        let stream = &ctx.accounts.stream;
+        msg!("🌊 Stream #{} claimed {} lamports", stream.id, amount);
+        log_fee(&fee);
+        msg!("   Total claimed: {}", stream.claimed);
+
+        Ok(())
+    }
+
+    /// 取消支付流
+    ///
+    /// # 功能
+    /// - 先把已累积但尚未领取的金额结算给收款人
+    /// - 然后关闭支付流账户，租金退还给所有者
+    /// - 已结束且领取完毕的支付流也通过此指令关闭
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 只结算当前能支付的部分（见 `settleable_amount`）：可用余额不足、时间锁或提款限额不允许、
+    ///   收款人已不在白名单中时，支付流照样关闭，未结算的部分作废
+    /// - 结算不会绕过时间锁和提款限额，否则所有者可以通过给自己的支付流提前取出被锁定的资金
+    ///
+    /// # 返回
+    /// - `Ok(())`: 取消成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::AllowlistAccountRequired)`: 开启了白名单但没有传入白名单账户
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "cancel_stream")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let claimable = ctx.accounts.stream.claimable(now)?;
+        let amount = settleable_amount(
+            &ctx.accounts.vault,
+            ctx.accounts.allowlist.as_deref(),
+            ctx.accounts.recipient.key,
+            claimable,
+        )?;
+
+        // 结算已累积的金额（当前能支付的部分）
+        if amount > 0 {
+            let recipient = ctx.accounts.recipient.to_account_info();
+            let fee = settle_stream(
+                &mut ctx.accounts.vault,
+                &mut ctx.accounts.stream,
+                ctx.accounts.allowlist.as_deref(),
+                &recipient,
+                amount,
+                &ctx.accounts.config,
+                ctx.accounts.treasury.as_deref(),
+            )?;
+            log_fee(&fee);
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        vault.open_streams = vault.open_streams.saturating_sub(1);
+
+        let stream = &ctx.accounts.stream;
+        msg!("🌊 Stream #{} cancelled", stream.id);
+        msg!("   Settled: {}", amount);
+        if claimable > amount {
+            msg!("   Unpaid: {} (forfeited)", claimable - amount);
+        }
+        msg!("   Total claimed: {}", stream.claimed);
+
+        // Anchor 通过 close = authority 约束自动关闭支付流账户
+
+        Ok(())
+    }
+
+    /// 质押闲置 SOL
+    ///
+    /// # 功能
+    /// - 从金库转出 `amount` lamports 创建质押账户（PDA: `[b"stake", vault]`）
+    /// - 质押账户的 staker / withdrawer 都是金库 PDA，并委托给 `vote_account`
+    /// - 记录到 `vault.staked_lamports`，提款时只能使用金库中的可用余额
+    ///
+    /// # 参数
+    /// - `amount`: 转入质押账户的总金额（lamports，包括质押账户的租金豁免保留金额）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 每个金库同时只能有一个质押账户，取回后才能再次质押
+    /// - 委托金额 = `amount` − 质押账户租金，必须满足质押程序的最低委托要求
+    /// - 质押本金离开金库账户，与提款一样受时间锁和提款限额限制（计入当前窗口的额度）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 质押成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::StakeAccountInUse)`: 已有质押账户
+    /// - `Err(VaultError::InvalidAmount)`: 金额不足以支付质押账户租金
+    /// - `Err(VaultError::StillLocked)`: 超过时间锁已解锁的金额
+    /// - `Err(VaultError::RateLimitExceeded)`: 超过提款限额
+    /// - `Err(VaultError::InsufficientFunds)`: 金库可用余额不足
+    pub fn stake_idle(ctx: Context<StakeIdle>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "stake_idle")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：多签模式下不能由单个密钥质押
+        require!(ctx.accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：同时只能有一个质押账户
+        require!(
+            ctx.accounts.vault.staked_lamports == 0 && ctx.accounts.stake_account.data_is_empty(),
+            VaultError::StakeAccountInUse
+        );
+
+        // 验证：金额必须大于质押账户的租金豁免保留金额
+        let stake_rent = ctx.accounts.rent.minimum_balance(StakeStateV2::size_of());
+        require!(amount > stake_rent, VaultError::InvalidAmount);
+
+        // 验证：时间锁和提款限额（与提款相同）
+        check_withdrawal(&mut ctx.accounts.vault, amount)?;
+
+        // 验证：金库可用余额（保留自身租金）必须足够
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let vault_rent = ctx.accounts.rent.minimum_balance(vault_info.data_len());
+        require!(
+            vault_info.lamports().saturating_sub(vault_rent) >= amount,
+            VaultError::InsufficientFunds
+        );
+
+        // 1. 从金库把 lamports 转入质押账户地址（程序可以直接减少自己账户的 lamports）
+        let stake_info = ctx.accounts.stake_account.to_account_info();
+        **vault_info.try_borrow_mut_lamports()? -= amount;
+        **stake_info.try_borrow_mut_lamports()? += amount;
+
+        // 2. 由质押账户 PDA 签名分配空间并交给质押程序
+        let vault_key = ctx.accounts.vault.key();
+        let stake_seeds = &[b"stake".as_ref(), vault_key.as_ref(), &[ctx.bumps.stake_account]];
+        let stake_signer = &[&stake_seeds[..]];
+
+        anchor_lang::system_program::allocate(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Allocate { account_to_allocate: stake_info.clone() },
+                stake_signer,
+            ),
+            StakeStateV2::size_of() as u64,
+        )?;
+        anchor_lang::system_program::assign(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Assign { account_to_assign: stake_info.clone() },
+                stake_signer,
+            ),
+            &stake_program::ID,
+        )?;
+
+        // 3. 初始化质押账户：staker 和 withdrawer 都是金库 PDA
+        invoke(
+            &stake_instruction::initialize(
+                stake_info.key,
+                &Authorized { staker: vault_key, withdrawer: vault_key },
+                &Lockup::default(),
+            ),
+            &[stake_info.clone(), ctx.accounts.rent.to_account_info()],
+        )?;
+
+        // 4. 由金库 PDA 签名委托给验证者
+        let vault = &ctx.accounts.vault;
+        let vault_seeds = &[
+            b"vault".as_ref(),
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes(),
+            &[vault.bump],
+        ];
+        invoke_signed(
+            &stake_instruction::delegate_stake(
+                stake_info.key,
+                &vault_key,
+                ctx.accounts.vote_account.key,
+            ),
+            &[
+                stake_info.clone(),
+                ctx.accounts.vote_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_config.to_account_info(),
+                vault_info.clone(),
+            ],
+            &[&vault_seeds[..]],
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.staked_lamports = amount;
+
+        msg!("🥩 Staked {} lamports", amount);
+        msg!("   Vote account: {}", ctx.accounts.vote_account.key());
+        msg!("   Stake account: {}", stake_info.key());
+        msg!("   Liquid balance: {}", vault.get_lamports());
+
+        Ok(())
+    }
+
+    /// 解除质押
+    ///
+    /// # 功能
+    /// - 停用金库的质押账户，冷却期（通常 1 个 epoch）结束后可以通过 `withdraw_stake` 取回
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 多签模式下也可以调用（资金只会回到金库）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 解除成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NoActiveStake)`: 金库没有质押
+    pub fn deactivate_stake(ctx: Context<DeactivateStake>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "deactivate_stake")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：必须有质押
+        require!(ctx.accounts.vault.staked_lamports > 0, VaultError::NoActiveStake);
+
+        let vault = &ctx.accounts.vault;
+        let seeds = &[
+            b"vault".as_ref(),
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        invoke_signed(
+            &stake_instruction::deactivate_stake(ctx.accounts.stake_account.key, &vault.key()),
+            &[
+                ctx.accounts.stake_account.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                vault.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        msg!("🥩 Stake deactivated");
+        msg!("   Staked: {}", vault.staked_lamports);
+
+        Ok(())
+    }
+
+    /// 取回质押
+    ///
+    /// # 功能
+    /// - 冷却期结束后把质押账户中的全部 lamports（本金 + 奖励）转回金库
+    /// - 质押账户随之关闭，`vault.staked_lamports` 清零
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 多签模式下也可以调用（资金只会回到金库）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 取回成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NoActiveStake)`: 金库没有质押
+    /// - 质押程序的错误（例如仍在冷却期）
+    pub fn withdraw_stake(ctx: Context<WithdrawStake>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "withdraw_stake")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：必须有质押
+        require!(ctx.accounts.vault.staked_lamports > 0, VaultError::NoActiveStake);
+
+        let stake_info = ctx.accounts.stake_account.to_account_info();
+        let amount = stake_info.lamports();
+
+        let vault = &ctx.accounts.vault;
+        let seeds = &[
+            b"vault".as_ref(),
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        invoke_signed(
+            &stake_instruction::withdraw(stake_info.key, &vault.key(), &vault.key(), amount, None),
+            &[
+                stake_info,
+                vault.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let rewards = amount.saturating_sub(vault.staked_lamports);
+        vault.staked_lamports = 0;
+
+        msg!("🥩 Withdrew {} lamports from stake", amount);
+        msg!("   Rewards: {}", rewards);
+        msg!("   Liquid balance: {}", vault.get_lamports());
+
+        Ok(())
+    }
+
+    /// 关闭金库并取回所有 SOL
+    ///
+    /// # 功能
+    /// - 关闭金库账户
+    /// - 将金库中所有剩余的 SOL 转给 `destination`（未传入时转给所有者）
+    /// - 释放账户占用的空间
+    ///
+    /// # 参数
+    /// - `require_empty`: 可选的余额阈值（lamports），
+    ///   传入时金库中租金以外的余额超过阈值则拒绝关闭（防止误关有余额的金库）
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 关闭后金库账户将不复存在
+    /// - 所有余额（包括租金）都会转给 `destination`
+    /// - 时间锁完全到期前不能关闭（否则可以绕过时间锁）
+    /// - 还有委托、支付流或代币账户时不能关闭（先 revoke_delegate / cancel_stream / close_token_account）
+    /// - 还有未完成的提案时不能关闭（先执行或 cancel_proposal）
+    /// - 可退款模式下还有未退款的存款回执时不能关闭
+    /// - 开启了操作历史或白名单时，历史账户和白名单账户一起关闭，租金同样转给 `destination`
+    /// - 金库从创建者的登记表中移除，登记表缩容后多出的租金同样转给 `destination`
+    /// - 此操作不可逆
+    ///
+    /// # 返回
+    /// - `Ok(())`: 关闭成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::StillLocked)`: 时间锁尚未完全到期
+    /// - `Err(VaultError::FundsStaked)`: 还有质押
+    /// - `Err(VaultError::LinkedAccountsOpen)`: 还有关联账户、提案或未退款的存款回执
+    /// - `Err(VaultError::VaultNotEmpty)`: 余额超过 `require_empty` 阈值
+    /// - `Err(VaultError::InvalidDestination)`: `destination` 是金库本身
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    /// - `Err(VaultError::AllowlistAccountRequired)`: 开启了白名单但没有传入白名单账户
+    pub fn close_vault(ctx: Context<CloseVault>, require_empty: Option<u64>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "close_vault")?;
+
+        let vault = &ctx.accounts.vault;
+
+        // 验证：多签模式下必须通过提案关闭
+        require!(vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 验证：时间锁已完全到期，没有质押和关联账户
+        ensure_closable(vault)?;
+
+        // 验证：require_empty 模式下，租金以外的余额不能超过阈值
+        let vault_info = vault.to_account_info();
+        let rent = Rent::get()?.minimum_balance(vault_info.data_len());
+        let remaining = vault_info.lamports().saturating_sub(rent);
+        if let Some(threshold) = require_empty {
+            require!(remaining <= threshold, VaultError::VaultNotEmpty);
+        }
+
+        // 未指定 destination 时转给所有者
+        let destination = match &ctx.accounts.destination {
+            Some(destination) => destination.to_account_info(),
+            None => ctx.accounts.authority.to_account_info(),
+        };
+
+        // 验证：不能转给金库本身（关闭后余额会丢失）
+        require_keys_neq!(destination.key(), vault.key(), VaultError::InvalidDestination);
+
+        emit!(VaultClosed {
+            vault: vault.key(),
+            authority: vault.authority,
+            destination: destination.key(),
+            amount: vault.get_lamports(),
+            total_deposits: vault.total_deposits,
+            total_withdrawals: vault.total_withdrawals,
+            slot: Clock::get()?.slot,
+        });
+
+        // 记录日志：显示金库最终统计
+        msg!("🔒 Closing vault '{}'", vault.vault_name);
+        msg!("   Total deposits: {}", vault.total_deposits);
+        msg!("   Total withdrawals: {}", vault.total_withdrawals);
+        msg!("   Remaining balance (excluding rent): {}", remaining);
+        msg!("   Final balance will be transferred to {}", destination.key());
+
+        // 关闭历史账户和白名单账户（如果开启了）
+        close_history(vault, ctx.accounts.history.as_ref(), &destination)?;
+        close_allowlist(vault, ctx.accounts.allowlist.as_ref(), &destination)?;
+
+        // 从登记表中移除
+        unregister_vault(&ctx.accounts.registry, &vault.key(), &destination)?;
+
+        // 关闭金库：转出所有 lamports，清空数据并交还给系统程序
+        ctx.accounts.vault.close(destination)?;
+
+        Ok(())
+    }
+
+    /// 为金库开通 SPL 代币托管
+    ///
+    /// # 功能
+    /// - 为指定 mint 创建金库代币账户（PDA，权限为金库 PDA）
+    /// - 创建该 mint 的存取款统计账户
+    ///
+    /// # 权限
+    /// - **只有金库所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 每个 mint 只能开通一次（PDA 已存在时 init 会失败）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 开通成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn init_token_account(ctx: Context<InitTokenAccount>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "init_token_account")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.open_token_accounts = vault.open_token_accounts.checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        let token_totals = &mut ctx.accounts.token_totals;
+
+        token_totals.vault = ctx.accounts.vault.key();
+        token_totals.mint = ctx.accounts.mint.key();
+        token_totals.total_deposits = 0;
+        token_totals.total_withdrawals = 0;
+        token_totals.bump = ctx.bumps.token_totals;
+
+        msg!("🪙 Token account opened for vault '{}'", ctx.accounts.vault.vault_name);
+        msg!("   Mint: {}", token_totals.mint);
+        msg!("   Vault token account: {}", ctx.accounts.vault_token_account.key());
+
+        Ok(())
+    }
+
+    /// 存入 SPL 代币
+    ///
+    /// # 功能
+    /// - 将代币从存款人的代币账户转入金库代币账户
+    /// - 更新该 mint 的总存款统计
+    ///
+    /// # 参数
+    /// - `amount`: 存款数量（代币最小单位）
+    ///
+    /// # 权限
+    /// - 任何人都可以向金库存入代币
+    ///
+    /// # 返回
+    /// - `Ok(())`: 存款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::InvalidAmount)`: 数量必须大于 0
+    /// - `Err(VaultError::Overflow)`: 累计存款数量溢出
+    pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "deposit_token")?;
+
+        // 验证：存款数量必须大于 0
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        // 由存款人签名，将代币转入金库代币账户
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // 更新该 mint 的总存款统计
+        let token_totals = &mut ctx.accounts.token_totals;
+        token_totals.total_deposits = token_totals.total_deposits.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        // 重新加载金库代币账户以获取最新余额（用于日志记录）
+        ctx.accounts.vault_token_account.reload()?;
+
+        msg!("💰 Deposited {} tokens", amount);
+        msg!("   Mint: {}", token_totals.mint);
+        msg!("   Total deposits: {}", token_totals.total_deposits);
+        msg!("   Vault token balance: {}", ctx.accounts.vault_token_account.amount);
+
+        Ok(())
+    }
+
+    /// 提取 SPL 代币
+    ///
+    /// # 功能
+    /// - 由金库 PDA 签名，将代币从金库代币账户转给接收者
+    /// - 更新该 mint 的总提款统计
+    ///
+    /// # 参数
+    /// - `amount`: 提款数量（代币最小单位）
+    ///
+    /// # 权限
+    /// - **只有金库所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 提款成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigRequired)`: 金库已启用多签
+    /// - `Err(VaultError::InvalidAmount)`: 数量必须大于 0
+    /// - `Err(VaultError::StillLocked)`: 数量超过当前已解锁的部分
+    /// - `Err(VaultError::InsufficientFunds)`: 金库代币余额不足
+    /// - `Err(VaultError::Overflow)`: 累计提款数量溢出
+    pub fn withdraw_token(ctx: Context<WithdrawToken>, amount: u64) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "withdraw_token")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：多签模式下必须通过提案提款
+        require!(ctx.accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+        // 执行转账（数量、时间锁、余额检查都在辅助函数中完成）
+        withdraw_tokens(
+            &ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &mut ctx.accounts.token_totals,
+            &ctx.accounts.receiver_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let token_totals = &ctx.accounts.token_totals;
+
+        msg!("💸 Withdrew {} tokens", amount);
+        msg!("   Mint: {}", token_totals.mint);
+        msg!("   Total withdrawals: {}", token_totals.total_withdrawals);
+        msg!("   Remaining token balance: {}", ctx.accounts.vault_token_account.amount);
+
+        Ok(())
+    }
+
+    /// 关闭代币托管
+    ///
+    /// # 功能
+    /// - 关闭余额为 0 的金库代币账户和该 mint 的统计账户，租金退还给所有者
+    /// - 关闭后该 mint 不再计入金库的关联账户，可以重新通过 `init_token_account` 开通
+    ///
+    /// # 权限
+    /// - **只有金库所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 关闭成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::TokenAccountNotEmpty)`: 金库代币账户还有余额
+    pub fn close_token_account(ctx: Context<CloseTokenAccount>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "close_token_account")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：代币必须已全部取出
+        require!(ctx.accounts.vault_token_account.amount == 0, VaultError::TokenAccountNotEmpty);
+
+        // 由金库 PDA 签名关闭代币账户
+        let vault = &ctx.accounts.vault;
+        let seeds = &[
+            b"vault".as_ref(),
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes(),
+            &[vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault_token_account.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.open_token_accounts = vault.open_token_accounts.saturating_sub(1);
+
+        let token_totals = &ctx.accounts.token_totals;
+
+        msg!("🪙 Token account closed for vault '{}'", vault.vault_name);
+        msg!("   Mint: {}", token_totals.mint);
+        msg!("   Total deposits: {}", token_totals.total_deposits);
+        msg!("   Total withdrawals: {}", token_totals.total_withdrawals);
+
+        // Anchor 通过 close = authority 约束自动关闭统计账户
+
+        Ok(())
+    }
+
+    /// 设置时间锁
+    ///
+    /// # 功能
+    /// - 为金库设置或收紧提款时间锁
+    /// - 支持到期一次性解锁（`Cliff`）和线性释放（`Linear`）
+    ///
+    /// # 参数
+    /// - `lock`: 新的时间锁计划
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 只能收紧、不能放宽：新计划在任意时刻解锁的比例都不能超过旧计划
+    /// - 一旦设置，无法移除（避免所有者私钥泄露后被直接解锁）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::InvalidLockSchedule)`: 计划的时间参数不合法（顺序错误或超出 0 ~ `MAX_LOCK_TIMESTAMP`）
+    /// - `Err(VaultError::LockCannotBeLoosened)`: 新计划比旧计划宽松
+    pub fn set_lock(ctx: Context<SetLock>, lock: LockSchedule) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_lock")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：时间参数合法
+        require!(lock.is_valid(), VaultError::InvalidLockSchedule);
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：只能收紧
+        if let Some(current) = &vault.lock {
+            require!(lock.is_at_least_as_strict_as(current), VaultError::LockCannotBeLoosened);
+        }
+
+        vault.lock = Some(lock);
+
+        msg!("⏳ Lock updated for vault '{}'", vault.vault_name);
+        msg!("   Schedule: {:?}", lock);
+
+        Ok(())
+    }
+
+    /// 启用多签
+    ///
+    /// # 功能
+    /// - 将金库切换为 M-of-N 多签模式
+    /// - 之后提款、转移所有权、关闭金库都必须通过提案执行
+    ///
+    /// # 参数
+    /// - `signers`: 多签成员（最多 10 个，不能重复）
+    /// - `threshold`: 执行提案所需的最少批准数
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    ///
+    /// # 重要说明
+    /// - 启用后无法由单个密钥修改或关闭多签
+    /// - 清除未完成的两步转移和继承设置（两者都会绕过多签转移所有权）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 启用成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigAlreadyEnabled)`: 已经是多签模式
+    /// - `Err(VaultError::InvalidMultisigConfig)`: 成员或阈值不合法
+    pub fn enable_multisig(
+        ctx: Context<EnableMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "enable_multisig")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：只能启用一次
+        require!(vault.multisig.is_none(), VaultError::MultisigAlreadyEnabled);
+
+        // 验证：成员数量、阈值、成员不重复
+        require!(
+            !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+            VaultError::InvalidMultisigConfig
+        );
+        require!(
+            threshold > 0 && threshold as usize <= signers.len(),
+            VaultError::InvalidMultisigConfig
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(!signers[..i].contains(signer), VaultError::InvalidMultisigConfig);
+        }
+
+        // 新的多签编号：之前的多签配置发起的提案不能再被批准或执行
+        vault.multisig_nonce = Clock::get()?.slot;
+
+        // 清除未完成的两步转移和继承设置，避免绕过多签
+        vault.pending_authority = None;
+        reset_inheritance(vault)?;
+
+        msg!("🔐 Multisig enabled for vault '{}'", vault.vault_name);
+        msg!("   Threshold: {} of {}", threshold, signers.len());
+
+        vault.multisig = Some(Multisig {
+            signers,
+            threshold,
+            proposal_count: 0,
+        });
+
+        Ok(())
+    }
+
+    /// 创建多签提案
+    ///
+    /// # 功能
+    /// - 多签成员发起一个提案（提款、代币提款、转移所有权或关闭金库）
+    /// - 发起人自动计入第一个批准
+    ///
+    /// # 参数
+    /// - `proposal_id`: 提案编号，必须等于金库当前的 `proposal_count`
+    /// - `action`: 提案要执行的操作
+    ///
+    /// # 权限
+    /// - 只有多签成员可以发起
+    ///
+    /// # 返回
+    /// - `Ok(())`: 创建成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigNotEnabled)`: 金库未启用多签
+    /// - `Err(VaultError::NotMultisigSigner)`: 调用者不是多签成员
+    /// - `Err(VaultError::InvalidProposalId)`: 提案编号不是下一个编号
+    /// - `Err(VaultError::InvalidAmount)`: 提款金额为 0
+    pub fn propose(ctx: Context<Propose>, proposal_id: u64, action: ProposalAction) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "propose")?;
+
+        let proposer = ctx.accounts.proposer.key();
+        let vault_key = ctx.accounts.vault.key();
+        let multisig_nonce = ctx.accounts.vault.multisig_nonce;
+        let multisig = ctx.accounts.vault.multisig.as_mut()
+            .ok_or(VaultError::MultisigNotEnabled)?;
+
+        // 验证：发起人必须是多签成员
+        require!(multisig.is_signer(&proposer), VaultError::NotMultisigSigner);
+
+        // 验证：提案编号必须连续
+        require!(proposal_id == multisig.proposal_count, VaultError::InvalidProposalId);
+
+        // 验证：提款金额必须大于 0
+        match action {
+            ProposalAction::Withdraw { amount, .. }
+            | ProposalAction::WithdrawToken { amount, .. } => {
+                require!(amount > 0, VaultError::InvalidAmount);
+            }
+            ProposalAction::TransferAuthority { .. } | ProposalAction::CloseVault { .. } => {}
+        }
+
+        multisig.proposal_count = multisig.proposal_count.checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        // 记录未完成的提案（关闭金库前必须执行或取消）
+        let vault = &mut ctx.accounts.vault;
+        if vault.has_fields(VAULT_VERSION_LINKED_ACCOUNTS) {
+            vault.open_proposals = vault.open_proposals.checked_add(1)
+                .ok_or(VaultError::Overflow)?;
+        }
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.vault = vault_key;
+        proposal.proposer = proposer;
+        proposal.id = proposal_id;
+        proposal.action = action;
+        proposal.approvals = vec![proposer];
+        proposal.multisig_nonce = multisig_nonce;
+        proposal.bump = ctx.bumps.proposal;
+
+        msg!("📝 Proposal #{} created", proposal_id);
+        msg!("   Action: {:?}", action);
+        msg!("   Proposer: {}", proposer);
+
+        Ok(())
+    }
+
+    /// 批准多签提案
+    ///
+    /// # 权限
+    /// - 只有多签成员可以批准，每个成员只能批准一次
+    ///
+    /// # 返回
+    /// - `Ok(())`: 批准成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::MultisigNotEnabled)`: 金库未启用多签
+    /// - `Err(VaultError::NotMultisigSigner)`: 调用者不是多签成员
+    /// - `Err(VaultError::StaleProposal)`: 提案由之前的多签配置发起
+    /// - `Err(VaultError::AlreadyApproved)`: 该成员已经批准过
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "approve")?;
+
+        let approver = ctx.accounts.approver.key();
+        let multisig = ctx.accounts.vault.multisig.as_ref()
+            .ok_or(VaultError::MultisigNotEnabled)?;
+
+        // 验证：必须是多签成员
+        require!(multisig.is_signer(&approver), VaultError::NotMultisigSigner);
+
+        let proposal = &mut ctx.accounts.proposal;
+
+        // 验证：提案属于当前的多签配置
+        require!(
+            proposal.multisig_nonce == ctx.accounts.vault.multisig_nonce,
+            VaultError::StaleProposal
+        );
+
+        // 验证：不能重复批准
+        require!(!proposal.approvals.contains(&approver), VaultError::AlreadyApproved);
+
+        proposal.approvals.push(approver);
+
+        msg!("👍 Proposal #{} approved by {}", proposal.id, approver);
+        msg!("   Approvals: {} / {}", proposal.approvals.len(), multisig.threshold);
+
+        Ok(())
+    }
+
+    /// 取消多签提案
+    ///
+    /// # 功能
+    /// - 关闭尚未执行的提案，租金退还给发起人
+    /// - 达不到阈值的提案可以通过取消释放，之后才能关闭金库
+    ///
+    /// # 权限
+    /// - 发起人可以取消自己的提案
+    /// - 已失效的提案（`multisig_nonce` 与金库不一致）任何人都可以关闭
+    ///
+    /// # 返回
+    /// - `Ok(())`: 取消成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotProposer)`: 调用者不是发起人，且提案未失效
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "cancel_proposal")?;
+
+        let proposal = &ctx.accounts.proposal;
+        let stale = proposal.multisig_nonce != ctx.accounts.vault.multisig_nonce;
+
+        // 验证：只有发起人可以取消仍然有效的提案
+        require!(
+            stale || ctx.accounts.closer.key() == proposal.proposer,
+            VaultError::NotProposer
+        );
+
+        // 失效的提案没有计入当前金库
+        if !stale {
+            release_proposal(&mut ctx.accounts.vault);
+        }
+
+        msg!("🗑️  Proposal #{} cancelled", proposal.id);
+        msg!("   Closer: {}", ctx.accounts.closer.key());
+
+        Ok(())
+    }
+
+    /// 执行提款提案
+    ///
+    /// # 功能
+    /// - 批准数达到阈值后，从金库提取 SOL 到提案指定的接收者
+    /// - 与 `withdraw` 使用相同的时间锁和租金豁免检查，同样收取手续费
+    /// - 执行后关闭提案账户，租金退还给发起人
+    ///
+    /// # 权限
+    /// - 任何多签成员都可以执行
+    ///
+    /// # 返回
+    /// - `Ok(())`: 执行成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotEnoughApprovals)`: 批准数未达到阈值
+    /// - `Err(VaultError::ProposalActionMismatch)`: 提案不是提款提案或接收者不匹配
+    /// - 以及 `withdraw` 的所有错误
+    pub fn execute_withdrawal(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "execute_withdrawal")?;
+
+        check_proposal_ready(&ctx.accounts.vault, &ctx.accounts.proposal, &ctx.accounts.executor)?;
+
+        let (receiver, amount) = match ctx.accounts.proposal.action {
+            ProposalAction::Withdraw { receiver, amount } => (receiver, amount),
+            _ => return err!(VaultError::ProposalActionMismatch),
+        };
+        require_keys_eq!(ctx.accounts.receiver.key(), receiver, VaultError::ProposalActionMismatch);
+
+        release_proposal(&mut ctx.accounts.vault);
+
+        let receiver_info = ctx.accounts.receiver.to_account_info();
+        let fee = withdraw_lamports(
+            &mut ctx.accounts.vault,
+            &receiver_info,
+            amount,
+            &ctx.accounts.config,
+            ctx.accounts.treasury.as_deref(),
+        )?;
+        let received = amount - fee.amount;
+
+        emit_withdrawn(&ctx.accounts.vault, ctx.accounts.executor.key(), receiver, received, fee.amount)?;
+
+        msg!("💸 Proposal #{} executed: withdrew {} lamports", ctx.accounts.proposal.id, received);
+        log_fee(&fee);
+        msg!("   Receiver: {}", receiver);
+        msg!("   Total withdrawals: {}", ctx.accounts.vault.total_withdrawals);
+
+        Ok(())
+    }
+
+    /// 执行代币提款提案
+    ///
+    /// # 功能
+    /// - 批准数达到阈值后，从金库代币账户转出代币
+    /// - 与 `withdraw_token` 使用相同的时间锁和余额检查
+    /// - 执行后关闭提案账户，租金退还给发起人
+    ///
+    /// # 权限
+    /// - 任何多签成员都可以执行
+    ///
+    /// # 返回
+    /// - `Ok(())`: 执行成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotEnoughApprovals)`: 批准数未达到阈值
+    /// - `Err(VaultError::ProposalActionMismatch)`: 提案不是代币提款提案或账户不匹配
+    /// - 以及 `withdraw_token` 的所有错误
+    pub fn execute_token_withdrawal(ctx: Context<ExecuteTokenWithdrawal>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "execute_token_withdrawal")?;
+
+        check_proposal_ready(&ctx.accounts.vault, &ctx.accounts.proposal, &ctx.accounts.executor)?;
+
+        let (mint, receiver_token_account, amount) = match ctx.accounts.proposal.action {
+            ProposalAction::WithdrawToken { mint, receiver_token_account, amount } => {
+                (mint, receiver_token_account, amount)
+            }
+            _ => return err!(VaultError::ProposalActionMismatch),
+        };
+        require_keys_eq!(ctx.accounts.token_totals.mint, mint, VaultError::ProposalActionMismatch);
+        require_keys_eq!(
+            ctx.accounts.receiver_token_account.key(),
+            receiver_token_account,
+            VaultError::ProposalActionMismatch
+        );
+
+        release_proposal(&mut ctx.accounts.vault);
+
+        withdraw_tokens(
+            &ctx.accounts.vault,
+            &mut ctx.accounts.vault_token_account,
+            &mut ctx.accounts.token_totals,
+            &ctx.accounts.receiver_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        msg!("💸 Proposal #{} executed: withdrew {} tokens", ctx.accounts.proposal.id, amount);
+        msg!("   Mint: {}", mint);
+        msg!("   Total withdrawals: {}", ctx.accounts.token_totals.total_withdrawals);
+
+        Ok(())
+    }
+
+    /// 执行转移所有权提案
+    ///
+    /// # 功能
+    /// - 批准数达到阈值后，将 `authority` 改为提案指定的新所有者
+    /// - 执行后关闭提案账户，租金退还给发起人
+    ///
+    /// # 权限
+    /// - 任何多签成员都可以执行
+    ///
+    /// # 返回
+    /// - `Ok(())`: 执行成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotEnoughApprovals)`: 批准数未达到阈值
+    /// - `Err(VaultError::ProposalActionMismatch)`: 提案不是转移所有权提案
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    pub fn execute_authority_transfer(ctx: Context<ExecuteAuthorityTransfer>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "execute_authority_transfer")?;
+
+        check_proposal_ready(&ctx.accounts.vault, &ctx.accounts.proposal, &ctx.accounts.executor)?;
+
+        let new_authority = match ctx.accounts.proposal.action {
+            ProposalAction::TransferAuthority { new_authority } => new_authority,
+            _ => return err!(VaultError::ProposalActionMismatch),
+        };
+
+        let vault = &mut ctx.accounts.vault;
+        release_proposal(vault);
+
+        let old_authority = vault.authority;
+        vault.authority = new_authority;
+        vault.pending_authority = None;
+        reset_inheritance(vault)?;
+
+        // 写入操作历史（actor 为新所有者，与 transfer_authority 一致）
+        record_history(
+            vault,
+            ctx.accounts.history.as_deref_mut(),
+            HistoryKind::AuthorityTransfer,
+            new_authority,
+            0,
+        )?;
+
+        emit!(AuthorityTransferred {
+            vault: vault.key(),
+            old_authority,
+            new_authority,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("🔑 Proposal #{} executed: authority transferred", ctx.accounts.proposal.id);
+        msg!("   From: {}", old_authority);
+        msg!("   To: {}", new_authority);
+
+        Ok(())
+    }
+
+    /// 执行关闭金库提案
+    ///
+    /// # 功能
+    /// - 批准数达到阈值后关闭金库，所有余额转给提案指定的接收地址
+    /// - 执行后关闭提案账户，租金退还给发起人
+    ///
+    /// # 权限
+    /// - 任何多签成员都可以执行
+    ///
+    /// # 返回
+    /// - `Ok(())`: 执行成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotEnoughApprovals)`: 批准数未达到阈值
+    /// - `Err(VaultError::ProposalActionMismatch)`: 提案不是关闭提案或接收地址不匹配
+    /// - `Err(VaultError::StillLocked)`: 时间锁尚未完全到期
+    /// - `Err(VaultError::FundsStaked)`: 还有质押
+    /// - `Err(VaultError::LinkedAccountsOpen)`: 还有关联账户、其他提案或未退款的存款回执
+    /// - `Err(VaultError::HistoryAccountRequired)`: 开启了操作历史但没有传入历史账户
+    /// - `Err(VaultError::AllowlistAccountRequired)`: 开启了白名单但没有传入白名单账户
+    pub fn execute_close(ctx: Context<ExecuteClose>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "execute_close")?;
+
+        check_proposal_ready(&ctx.accounts.vault, &ctx.accounts.proposal, &ctx.accounts.executor)?;
+
+        let destination = match ctx.accounts.proposal.action {
+            ProposalAction::CloseVault { destination } => destination,
+            _ => return err!(VaultError::ProposalActionMismatch),
+        };
+        require_keys_eq!(ctx.accounts.destination.key(), destination, VaultError::ProposalActionMismatch);
+
+        // 验证：时间锁已完全到期，没有质押和关联账户（正在执行的提案除外）
+        release_proposal(&mut ctx.accounts.vault);
+        ensure_closable(&ctx.accounts.vault)?;
+
+        let vault = &ctx.accounts.vault;
+        emit!(VaultClosed {
+            vault: vault.key(),
+            authority: vault.authority,
+            destination,
+            amount: vault.get_lamports(),
+            total_deposits: vault.total_deposits,
+            total_withdrawals: vault.total_withdrawals,
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("🔒 Proposal #{} executed: closing vault '{}'", ctx.accounts.proposal.id, ctx.accounts.vault.vault_name);
+        msg!("   Total deposits: {}", ctx.accounts.vault.total_deposits);
+        msg!("   Total withdrawals: {}", ctx.accounts.vault.total_withdrawals);
+        msg!("   Final balance will be transferred to {}", destination);
+
+        // 关闭历史账户和白名单账户（如果开启了）
+        close_history(
+            &ctx.accounts.vault,
+            ctx.accounts.history.as_ref(),
+            &ctx.accounts.destination,
+        )?;
+        close_allowlist(
+            &ctx.accounts.vault,
+            ctx.accounts.allowlist.as_ref(),
+            &ctx.accounts.destination,
+        )?;
+
+        // 从登记表中移除
+        unregister_vault(
+            &ctx.accounts.registry,
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.destination,
+        )?;
+
+        // 关闭金库：转出所有 lamports，清空数据并交还给系统程序
+        ctx.accounts.vault.close(ctx.accounts.destination.to_account_info())?;
+
+        Ok(())
+    }
+
+    /// 迁移旧版金库
+    ///
+    /// # 功能
+    /// - 将旧版本（包括 125 字节、无 version 字段的版本 0）金库扩容到当前布局
+    /// - 新增字段全部取默认值，`version` 设为 `VAULT_VERSION`
+    /// - 账户已经是当前大小、只是版本号落后时只更新版本号
+    ///
+    /// # 权限
+    /// - 任何人都可以调用，由 `payer` 支付扩容所需的额外租金
+    ///
+    /// # 重要说明
+    /// - 迁移前旧版金库仍可以存款、提款和关闭
+    /// - 需要写入新字段的指令（时间锁、多签、两步转移等）会返回 `MigrationRequired`
+    ///
+    /// # 返回
+    /// - `Ok(())`: 迁移成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::AlreadyMigrated)`: 金库已经是当前大小和版本
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "migrate_vault")?;
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let old_len = vault_info.data_len();
+        let new_len = 8 + Vault::INIT_SPACE;
+
+        // 验证：账户小于当前布局，或者版本号落后
+        require!(
+            old_len < new_len || ctx.accounts.vault.version < VAULT_VERSION,
+            VaultError::AlreadyMigrated
+        );
+
+        // 扩容：先补足新大小的租金，再调整账户数据长度
+        if old_len < new_len {
+            let shortfall = Rent::get()?
+                .minimum_balance(new_len)
+                .saturating_sub(vault_info.lamports());
+            if shortfall > 0 {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: vault_info.clone(),
+                    },
+                );
+                anchor_lang::system_program::transfer(cpi_context, shortfall)?;
+            }
+            vault_info.resize(new_len)?;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        let old_version = vault.version;
+        vault.version = VAULT_VERSION;
+
+        // 退出时按当前布局写回
+
+        msg!("📦 Vault '{}' migrated", vault.vault_name);
+        msg!("   Version: {} -> {}", old_version, VAULT_VERSION);
+        msg!("   Size: {} -> {} bytes", old_len, new_len);
+
+        Ok(())
+    }
+
+    /// 设置守护者
+    ///
+    /// # 功能
+    /// - 设置（或移除）可以紧急暂停金库的守护者密钥
+    ///
+    /// # 参数
+    /// - `guardian`: 新的守护者，None 表示移除
+    ///
+    /// # 权限
+    /// - **只有当前所有者**可以调用（通过 has_one = authority 约束验证）
+    /// - 已有守护者时，当前守护者也必须签名（`current_guardian`），
+    ///   防止所有者私钥泄露后先移除守护者
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    /// - `Err(VaultError::NotGuardian)`: 缺少当前守护者的签名
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Option<Pubkey>) -> Result<()> {
+        // 验证：金库未被暂停
+        ensure_not_paused(&ctx.accounts.vault, "set_guardian")?;
+        record_heartbeat(&mut ctx.accounts.vault)?;
+
+        // 验证：已有守护者时必须由其共同签名
+        if let Some(current) = ctx.accounts.vault.guardian {
+            let signer = ctx.accounts.current_guardian.as_ref()
+                .ok_or(VaultError::NotGuardian)?;
+            require_keys_eq!(signer.key(), current, VaultError::NotGuardian);
+        }
+
+        ctx.accounts.vault.guardian = guardian;
+
+        msg!("🛡️ Guardian set to {:?}", guardian);
+
+        Ok(())
+    }
+
+    /// 紧急暂停金库
+    ///
+    /// # 功能
+    /// - 冻结金库：除 `unpause` 外的所有指令都会返回 `VaultPaused`
+    /// - 发出 `VaultPaused` 事件
+    ///
+    /// # 权限
+    /// - **只有守护者**可以调用
+    ///
+    /// # 返回
+    /// - `Ok(())`: 暂停成功
+    /// - `Err(VaultError::NotGuardian)`: 调用者不是守护者
+    /// - `Err(VaultError::VaultPaused)`: 金库已被暂停
+    pub fn pause(ctx: Context<Pause>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：必须是守护者
+        require!(
+            vault.guardian == Some(ctx.accounts.guardian.key()),
+            VaultError::NotGuardian
+        );
+
+        // 验证：不能重复暂停
+        require!(!vault.paused, VaultError::VaultPaused);
+
+        vault.paused = true;
+
+        emit!(VaultPaused {
+            vault: vault.key(),
+            guardian: ctx.accounts.guardian.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("⏸️ Vault paused by guardian {}", ctx.accounts.guardian.key());
+
+        Ok(())
+    }
+
+    /// 解除暂停
+    ///
+    /// # 权限
+    /// - **所有者和守护者**必须同时签名
+    ///
+    /// # 返回
+    /// - `Ok(())`: 解除成功
+    /// - `Err(VaultError::NotGuardian)`: 守护者签名不匹配
+    /// - `Err(VaultError::VaultNotPaused)`: 金库未被暂停
+    pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        // 验证：必须是守护者
+        require!(
+            vault.guardian == Some(ctx.accounts.guardian.key()),
+            VaultError::NotGuardian
+        );
+
+        // 验证：金库必须处于暂停状态
+        require!(vault.paused, VaultError::VaultNotPaused);
+
+        vault.paused = false;
+        record_heartbeat(vault)?;
+
+        emit!(VaultUnpaused {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            guardian: ctx.accounts.guardian.key(),
+            slot: Clock::get()?.slot,
+        });
+
+        msg!("▶️ Vault unpaused");
+
+        Ok(())
+    }
+
+    /// 创建全局配置
+    ///
+    /// # 功能
+    /// - 创建全局唯一的 `VaultConfig`（PDA: `[b"config"]`），调用者成为配置管理员
+    /// - 设置提款手续费和接收手续费的协议金库（`treasury` 账户）
+    ///
+    /// # 参数
+    /// - `withdraw_fee_bps`: 提款手续费（基点，最多 `MAX_WITHDRAW_FEE_BPS`）
+    ///
+    /// # 权限
+    /// - **只有程序的升级权限**可以调用（防止部署后被抢先创建）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 创建成功
+    /// - `Err(VaultError::NotUpgradeAuthority)`: 调用者不是程序的升级权限
+    /// - `Err(VaultError::FeeTooHigh)`: 手续费超过上限
+    /// - `Err(VaultError::InvalidTreasury)`: 协议金库不存在或余额低于租金豁免最低余额
+    pub fn init_config(ctx: Context<InitConfig>, withdraw_fee_bps: u16) -> Result<()> {
+        // 验证：program_data 属于本程序，且调用者是升级权限
+        require_keys_eq!(
+            ctx.accounts.program.programdata_address()?.unwrap_or_default(),
+            ctx.accounts.program_data.key(),
+            VaultError::NotUpgradeAuthority
+        );
+        require!(
+            ctx.accounts.program_data.upgrade_authority_address == Some(ctx.accounts.admin.key()),
+            VaultError::NotUpgradeAuthority
+        );
+
+        // 验证：手续费不超过上限
+        require!(withdraw_fee_bps <= MAX_WITHDRAW_FEE_BPS, VaultError::FeeTooHigh);
+
+        // 验证：协议金库可以接收任意金额的手续费
+        let treasury = ctx.accounts.treasury.key();
+        validate_treasury(&ctx.accounts.treasury)?;
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.withdraw_fee_bps = withdraw_fee_bps;
+        config.bump = ctx.bumps.config;
+
+        emit_config_updated(config)?;
+
+        msg!("⚙️ Vault config initialized");
+        msg!("   Treasury: {}", treasury);
+        msg!("   Withdraw fee: {} bps", withdraw_fee_bps);
+
+        Ok(())
+    }
+
+    /// 设置提款手续费
+    ///
+    /// # 参数
+    /// - `withdraw_fee_bps`: 提款手续费（基点，0 表示不收取，最多 `MAX_WITHDRAW_FEE_BPS`）
+    ///
+    /// # 权限
+    /// - **只有配置管理员**可以调用（通过 has_one = admin 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::FeeTooHigh)`: 手续费超过上限
+    pub fn set_withdraw_fee(ctx: Context<UpdateConfig>, withdraw_fee_bps: u16) -> Result<()> {
+        // 验证：手续费不超过上限
+        require!(withdraw_fee_bps <= MAX_WITHDRAW_FEE_BPS, VaultError::FeeTooHigh);
+
+        let config = &mut ctx.accounts.config;
+        let old_fee = config.withdraw_fee_bps;
+        config.withdraw_fee_bps = withdraw_fee_bps;
+
+        emit_config_updated(config)?;
+
+        msg!("⚙️ Withdraw fee: {} -> {} bps", old_fee, withdraw_fee_bps);
+
+        Ok(())
+    }
+
+    /// 设置协议金库
+    ///
+    /// # 功能
+    /// - 把 `treasury` 账户设为新的协议金库（之后的手续费转到这里）
+    ///
+    /// # 权限
+    /// - **只有配置管理员**可以调用（通过 has_one = admin 约束验证）
+    ///
+    /// # 返回
+    /// - `Ok(())`: 设置成功
+    /// - `Err(VaultError::InvalidTreasury)`: 协议金库不存在或余额低于租金豁免最低余额
+    pub fn set_treasury(ctx: Context<SetTreasury>) -> Result<()> {
+        let treasury = ctx.accounts.treasury.key();
+        validate_treasury(&ctx.accounts.treasury)?;
+
+        let config = &mut ctx.accounts.config;
+        let old_treasury = config.treasury;
+        config.treasury = treasury;
+
+        emit_config_updated(config)?;
+
+        msg!("⚙️ Treasury: {} -> {}", old_treasury, treasury);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// 金库名称
+// ============================================================================
+//
+// 程序和 Rust 客户端（client-rs/token-vault）共用，保证两边对名称的校验
+// 和 PDA 派生完全一致。
+
+/// 金库名称的最大长度（字节）
+pub const MAX_VAULT_NAME_LEN: usize = 32;
+
+/// 检查金库名称是否为规范形式
+///
+/// 规范形式：1 到 32 个字符，只包含小写 ASCII 字母、数字、`-` 和 `_`。
+///
+/// # 返回
+/// - `Err(VaultError::NameTooLong)`: 超过 32 字节
+/// - `Err(VaultError::InvalidVaultName)`: 为空或包含其他字符（大写、空白、非 ASCII 等）
+pub fn validate_vault_name(name: &str) -> std::result::Result<(), VaultError> {
+    if name.len() > MAX_VAULT_NAME_LEN {
+        return Err(VaultError::NameTooLong);
+    }
+
+    let allowed = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_';
+    if name.is_empty() || !name.bytes().all(allowed) {
+        return Err(VaultError::InvalidVaultName);
+    }
+
+    Ok(())
+}
+
+/// 将用户输入转换为规范形式的金库名称
+///
+/// 去掉首尾空白并转为小写后再校验，例如 `" My-Vault "` → `"my-vault"`。
+pub fn normalize_vault_name(input: &str) -> std::result::Result<String, VaultError> {
+    let name = input.trim().to_ascii_lowercase();
+    validate_vault_name(&name)?;
+    Ok(name)
+}
+
+/// 计算金库 PDA（与 `Initialize` 的 seeds 一致）
+///
+/// `vault_name` 必须已经是规范形式。
+pub fn find_vault_address(original_authority: &Pubkey, vault_name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"vault", original_authority.as_ref(), vault_name.as_bytes()],
+        &crate::ID,
+    )
+}
+
+/// 计算创建者的金库登记表 PDA（与 `Initialize` 的 seeds 一致）
+pub fn find_registry_address(original_authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry", original_authority.as_ref()], &crate::ID)
+}
+
+/// 计算全局配置 PDA（与 `InitConfig` 的 seeds 一致）
+pub fn find_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], &crate::ID)
+}
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 写入新金库的初始状态
+///
+/// `initialize` 和 `initialize_with_payer` 共用；`authority` 同时作为当前所有者和原始创建者
+fn init_vault(vault: &mut Account<Vault>, authority: Pubkey, vault_name: String, bump: u8) -> Result<()> {
+    // 验证：金库名称必须是规范形式（非空、最多 32 字符、小写字母 / 数字 / - / _）
+    validate_vault_name(&vault_name)?;
+
+    // 设置当前所有者（可以通过 propose_authority / accept_authority 转移）
+    vault.authority = authority;
+
+    // 设置原始创建者（永不改变，用于 PDA seeds 计算）
+    vault.original_authority = authority;
+
+    // 设置金库名称
+    vault.vault_name = vault_name.clone();
+
+    // 初始化统计数据：总存款金额
+    vault.total_deposits = 0;
+
+    // 初始化统计数据：总提款金额
+    vault.total_withdrawals = 0;
+
+    // 保存 PDA bump seed（用于后续签名）
+    vault.bump = bump;
+
+    // 默认不加锁（可通过 set_lock 设置）
+    vault.lock = None;
+
+    // 默认单签模式（可通过 enable_multisig 启用多签）
+    vault.multisig = None;
+    vault.multisig_nonce = 0;
+
+    // 没有待接受的新所有者；默认禁用一步转移
+    vault.pending_authority = None;
+    vault.one_step_transfer = false;
+
+    // 默认不可退款（可通过 enable_refunds 开启）
+    vault.refundable = false;
+
+    // 默认不限额（可通过 set_rate_limit 设置）
+    vault.rate_limit = None;
+
+    // 默认不限制接收者（可通过 enable_allowlist 开启）
+    vault.receiver_allowlist = false;
+
+    // 默认没有守护者（可通过 set_guardian 设置）
+    vault.guardian = None;
+    vault.paused = false;
+
+    // 还没有创建过支付流
+    vault.stream_count = 0;
+
+    // 还没有质押（可通过 stake_idle 质押闲置 SOL）
+    vault.staked_lamports = 0;
+
+    // 还没有关联账户
+    vault.open_delegates = 0;
+    vault.open_streams = 0;
+    vault.open_token_accounts = 0;
+
+    // 没有提案和存款回执
+    vault.open_proposals = 0;
+    vault.receipt_balance = 0;
+
+    // 默认不记录操作历史（可通过 enable_history 开启）
+    vault.history_enabled = false;
+
+    // 默认存款不需要参考号（可通过 set_require_reference 开启）
+    vault.require_reference = false;
+
+    // 默认不限制存款金额和余额（可通过 set_deposit_limits 设置）
+    vault.min_deposit = 0;
+    vault.max_balance = None;
+
+    // 默认没有受益人（可通过 set_beneficiary 设置）；创建金库也是所有者操作
+    vault.beneficiary = None;
+    vault.inactivity_period = 0;
+    vault.last_heartbeat = Clock::get()?.unix_timestamp;
+
+    // 新金库直接使用当前布局
+    vault.version = VAULT_VERSION;
+
+    // 发出事件（供索引服务通过 IDL 解码）
+    emit!(VaultInitialized {
+        vault: vault.key(),
+        authority: vault.authority,
+        vault_name: vault_name.clone(),
+        slot: Clock::get()?.slot,
+    });
+
+    // 记录日志：金库创建成功
+    msg!("✅ Vault '{}' initialized", vault_name);
+    msg!("   Authority: {}", vault.authority);
+    msg!("   Vault PDA: {}", vault.key());
+
+    Ok(())
+}
+
+/// 把新金库登记到创建者的登记表
+///
+/// 登记表按实际数量分配空间：每登记一个金库扩容 32 字节，补足的租金由 `payer` 支付。
+/// 创建者的第一个金库会先创建登记表 PDA。
+fn register_vault<'info>(
+    registry_info: &AccountInfo<'info>,
+    owner: Pubkey,
+    vault: Pubkey,
+    bump: u8,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    let mut registry = if registry_info.owner == &system_program::ID {
+        // 登记表 PDA 还不存在：由本程序创建（空间在下面扩容）
+        create_registry(registry_info, owner, bump, &payer, &system_program)?;
+        VaultRegistry {
+            owner,
+            vaults: Vec::new(),
+            bump,
+        }
+    } else {
+        // 已存在的登记表：UncheckedAccount 不会自动检查所有者和判别器
+        require_keys_eq!(*registry_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        VaultRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?
+    };
+
+    require!(registry.vaults.len() < MAX_REGISTRY_VAULTS, VaultError::RegistryFull);
+
+    // 扩容：先补足新大小的租金，再调整账户数据长度
+    let new_len = VaultRegistry::space(registry.vaults.len() + 1);
+    let shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(registry_info.lamports());
+    if shortfall > 0 {
+        let cpi_context = CpiContext::new(
+            system_program,
+            anchor_lang::system_program::Transfer {
+                from: payer,
+                to: registry_info.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, shortfall)?;
+    }
+    registry_info.resize(new_len)?;
+
+    registry.vaults.push(vault);
+    {
+        let mut data = registry_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        registry.try_serialize(&mut writer)?;
+    }
+
+    msg!("📇 Vault registered ({} total)", registry.vaults.len());
+
+    Ok(())
+}
+
+/// 创建空的登记表 PDA（`VaultRegistry::space(0)`，租金由 `payer` 支付）
+///
+/// 与 Anchor 的 `init` 相同：PDA 已经被转入过 lamports 时 `create_account` 会失败，
+/// 改为补足租金后 `allocate` + `assign`。
+fn create_registry<'info>(
+    registry_info: &AccountInfo<'info>,
+    owner: Pubkey,
+    bump: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[b"registry", owner.as_ref(), &[bump]]];
+    let space = VaultRegistry::space(0);
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if registry_info.lamports() == 0 {
+        let cpi_context = CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: registry_info.clone(),
+            },
+            signer_seeds,
+        );
+        return anchor_lang::system_program::create_account(cpi_context, rent, space as u64, &crate::ID);
+    }
+
+    let shortfall = rent.saturating_sub(registry_info.lamports());
+    if shortfall > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: registry_info.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, shortfall)?;
+    }
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.clone(),
+        anchor_lang::system_program::Allocate {
+            account_to_allocate: registry_info.clone(),
+        },
+        signer_seeds,
+    );
+    anchor_lang::system_program::allocate(cpi_context, space as u64)?;
+    let cpi_context = CpiContext::new_with_signer(
+        system_program.clone(),
+        anchor_lang::system_program::Assign {
+            account_to_assign: registry_info.clone(),
+        },
+        signer_seeds,
+    );
+    anchor_lang::system_program::assign(cpi_context, &crate::ID)
+}
+
+/// 把金库从登记表中移除
+///
+/// 登记表缩容，多出的租金转给 `destination`。创建者还没有登记表，或金库不在登记表中
+/// （登记表出现之前创建的金库）时直接跳过。
+fn unregister_vault(
+    registry_info: &AccountInfo,
+    vault: &Pubkey,
+    destination: &AccountInfo,
+) -> Result<()> {
+    // 登记表 PDA 还没有被本程序创建
+    if registry_info.owner != &crate::ID {
+        return Ok(());
+    }
+
+    let mut registry = VaultRegistry::try_deserialize(&mut &registry_info.try_borrow_data()?[..])?;
+    let Some(index) = registry.vaults.iter().position(|v| v == vault) else {
+        return Ok(());
+    };
+    registry.vaults.swap_remove(index);
+
+    // 缩容：调整账户数据长度并写回，退还多出的租金
+    let new_len = VaultRegistry::space(registry.vaults.len());
+    registry_info.resize(new_len)?;
+    {
+        let mut data = registry_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        registry.try_serialize(&mut writer)?;
+    }
+    let excess = registry_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(new_len));
+    **registry_info.try_borrow_mut_lamports()? -= excess;
+    **destination.try_borrow_mut_lamports()? += excess;
+
+    msg!("📇 Vault unregistered ({} remaining)", registry.vaults.len());
+
+    Ok(())
+}
+
+/// 记录所有者的活跃时间（继承功能的心跳）
+///
+/// 所有者的每个操作都会调用；版本 7 之前的金库没有 `last_heartbeat` 字段，直接跳过，
+/// 不会因此要求迁移
+fn record_heartbeat(vault: &mut Vault) -> Result<()> {
+    if vault.has_fields(VAULT_VERSION_INHERITANCE) {
+        vault.last_heartbeat = Clock::get()?.unix_timestamp;
+    }
+    Ok(())
+}
+
+/// 所有者变更后清除继承设置并重新开始计时
+///
+/// 受益人是原所有者指定的，新所有者需要自己重新设置
+fn reset_inheritance(vault: &mut Vault) -> Result<()> {
+    if vault.has_fields(VAULT_VERSION_INHERITANCE) {
+        vault.beneficiary = None;
+        vault.inactivity_period = 0;
+        vault.last_heartbeat = Clock::get()?.unix_timestamp;
+    }
+    Ok(())
+}
+
+/// 检查存款是否符合金库的存款限制
+///
+/// - 金额不能低于 `min_deposit`
+/// - 存款后的余额（可用余额 + 质押中的本金）不能超过 `max_balance`
+fn ensure_deposit_within_limits(vault: &Account<Vault>, amount: u64) -> Result<()> {
+    require!(amount >= vault.min_deposit, VaultError::DepositTooSmall);
+
+    if let Some(max_balance) = vault.max_balance {
+        let balance_after = available_balance(vault)?
+            .checked_add(vault.staked_lamports)
+            .and_then(|balance| balance.checked_add(amount))
+            .ok_or(VaultError::Overflow)?;
+        require!(balance_after <= max_balance, VaultError::MaxBalanceExceeded);
+    }
+
+    Ok(())
+}
+
+/// 把存款参考号格式化为十六进制字符串（用于日志）
+fn reference_hex(reference: &[u8; 32]) -> String {
+    reference.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 从金库提取 SOL
+///
+/// `withdraw` 系列、委托提款、多签提款和支付流共用，负责：
+/// - 金额必须大于 0
+/// - 时间锁（只能提取已解锁的部分）
+/// - 提款限额（累计到当前时间窗口）
+/// - 按全局配置收取手续费：从 `amount` 中扣除转给协议金库，接收者收到 `amount - fee`
+/// - 保留租金豁免最低余额并转账（见 `transfer_from_vault`）
+///
+/// 返回收取的手续费（没有配置或费率为 0 时金额为 0）
+fn withdraw_lamports(
+    vault: &mut Account<Vault>,
+    receiver: &AccountInfo,
+    amount: u64,
+    config: &AccountInfo,
+    treasury: Option<&AccountInfo>,
+) -> Result<WithdrawalFee> {
+    // 计算手续费（全局配置不存在或费率为 0 时不收取）
+    let fee = withdrawal_fee(config, amount)?;
+
+    // 时间锁和提款限额按含手续费的总金额计算
+    check_withdrawal(vault, amount)?;
+    transfer_from_vault(vault, receiver, amount - fee.amount)?;
+    if fee.amount > 0 {
+        let treasury = treasury.ok_or(VaultError::TreasuryMismatch)?;
+        require_keys_eq!(treasury.key(), fee.treasury, VaultError::TreasuryMismatch);
+        transfer_from_vault(vault, treasury, fee.amount)?;
+    }
+
+    Ok(fee)
+}
+
+/// 检查提款金额、时间锁，并记录提款限额
+///
+/// 提款之外，`stake_idle` 转出质押本金前也会调用
+fn check_withdrawal(vault: &mut Account<Vault>, amount: u64) -> Result<()> {
+    // 验证：提款金额必须大于 0
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    // 验证：时间锁（如果设置了）
+    if let Some(lock) = &vault.lock {
+        let now = Clock::get()?.unix_timestamp;
+        let unlocked = lock.unlocked_amount(vault.total_deposits, vault.total_withdrawals, now);
+        require!(amount <= unlocked, VaultError::StillLocked);
+    }
+
+    // 验证并记录：提款限额（如果设置了）
+    if let Some(limit) = vault.rate_limit.as_mut() {
+        let now = Clock::get()?.unix_timestamp;
+        limit.consume(amount, now)?;
+    }
+
+    Ok(())
+}
+
+/// 提款手续费
+struct WithdrawalFee {
+    /// 手续费金额（lamports）
+    amount: u64,
+    /// 费率（基点）
+    bps: u16,
+    /// 接收手续费的协议金库
+    treasury: Pubkey,
+}
+
+/// 按全局配置计算提款手续费（向下取整）
+///
+/// 配置账户还没有创建时不收取手续费。
+fn withdrawal_fee(config: &AccountInfo, amount: u64) -> Result<WithdrawalFee> {
+    if config.owner != &crate::ID || config.data_is_empty() {
+        return Ok(WithdrawalFee { amount: 0, bps: 0, treasury: Pubkey::default() });
+    }
+
+    let config = VaultConfig::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+    let fee = (amount as u128 * config.withdraw_fee_bps as u128 / MAX_BPS as u128) as u64;
+
+    Ok(WithdrawalFee { amount: fee, bps: config.withdraw_fee_bps, treasury: config.treasury })
+}
+
+/// 检查协议金库可以接收手续费
+///
+/// 手续费直接加到协议金库的余额上：如果协议金库不存在或余额低于租金豁免最低余额，
+/// 小额手续费会让它停留在租金豁免以下，运行时会拒绝整笔提款交易。
+fn validate_treasury(treasury: &AccountInfo) -> Result<()> {
+    require!(!treasury.executable, VaultError::InvalidTreasury);
+    require!(
+        treasury.lamports() > 0
+            && Rent::get()?.is_exempt(treasury.lamports(), treasury.data_len()),
+        VaultError::InvalidTreasury
+    );
+    Ok(())
+}
+
+/// 记录收取的手续费（没有收取时不记录）
+fn log_fee(fee: &WithdrawalFee) {
+    if fee.amount > 0 {
+        msg!("   Fee: {} lamports ({} bps) to {}", fee.amount, fee.bps, fee.treasury);
+    }
+}
+
+/// 发出 `VaultConfigUpdated` 事件
+fn emit_config_updated(config: &VaultConfig) -> Result<()> {
+    emit!(VaultConfigUpdated {
+        admin: config.admin,
+        treasury: config.treasury,
+        withdraw_fee_bps: config.withdraw_fee_bps,
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(())
+}
+
+/// 检查金库是否被暂停
+///
+/// 暂停时记录被拒绝的指令名并返回错误。
+/// 被拒绝的交易会失败，不发出事件；暂停状态由 `pause` 的 `VaultPaused` 事件表示。
+fn ensure_not_paused(vault: &Account<Vault>, instruction: &str) -> Result<()> {
+    if vault.paused {
+        msg!("⏸️ {} rejected: vault is paused", instruction);
+        return err!(VaultError::VaultPaused);
+    }
+
+    Ok(())
+}
+
+/// 向收款人结算支付流
+///
+/// `claim_stream` 和 `cancel_stream` 共用：检查接收者白名单，
+/// 通过 `withdraw_lamports` 转账（收取手续费）并记录已领取金额（含手续费），返回手续费。
+fn settle_stream(
+    vault: &mut Account<Vault>,
+    stream: &mut PaymentStream,
+    allowlist: Option<&ReceiverAllowlist>,
+    recipient: &AccountInfo,
+    amount: u64,
+    config: &AccountInfo,
+    treasury: Option<&AccountInfo>,
+) -> Result<WithdrawalFee> {
+    ensure_receiver_allowed(vault, allowlist, recipient.key)?;
+    let fee = withdraw_lamports(vault, recipient, amount, config, treasury)?;
+
+    stream.claimed = stream.claimed.checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+
+    emit_withdrawn(vault, stream.recipient, stream.recipient, amount - fee.amount, fee.amount)?;
+
+    Ok(fee)
+}
+
+/// 取消支付流时可以结算给收款人的金额
+///
+/// 不超过可用余额、时间锁已解锁的额度和提款限额的剩余额度；收款人不在白名单中时为 0。
+/// 这样结算仍然满足 `withdraw_lamports` 的所有检查，取消也不会因为这些检查失败
+/// （否则支付流永远无法关闭，`open_streams` 不归零，金库也无法关闭）。
+fn settleable_amount(
+    vault: &Account<Vault>,
+    allowlist: Option<&ReceiverAllowlist>,
+    recipient: &Pubkey,
+    claimable: u64,
+) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+
+    // 白名单账户仍然必须传入，避免所有者通过省略账户跳过结算
+    if vault.receiver_allowlist {
+        let allowlist = allowlist.ok_or(VaultError::AllowlistAccountRequired)?;
+        require_keys_eq!(allowlist.vault, vault.key(), VaultError::AllowlistAccountRequired);
+        if !allowlist.is_allowed(recipient, now) {
+            return Ok(0);
+        }
+    }
+
+    let mut amount = claimable.min(available_balance(vault)?);
+    if let Some(lock) = &vault.lock {
+        amount = amount.min(lock.unlocked_amount(vault.total_deposits, vault.total_withdrawals, now));
+    }
+    if let Some(limit) = &vault.rate_limit {
+        amount = amount.min(limit.remaining(now));
+    }
+
+    Ok(amount)
+}
+
+/// 发出 `Withdrawn` 事件（在转账完成后调用，余额为提款后的余额）
+fn emit_withdrawn(
+    vault: &Account<Vault>,
+    withdrawer: Pubkey,
+    receiver: Pubkey,
+    amount: u64,
+    fee: u64,
+) -> Result<()> {
+    emit!(Withdrawn {
+        vault: vault.key(),
+        withdrawer,
+        receiver,
+        amount,
+        fee,
+        total_withdrawals: vault.total_withdrawals,
+        balance: vault.get_lamports(),
+        slot: Clock::get()?.slot,
+    });
+
+    Ok(())
+}
+
+/// 检查接收者白名单
+///
+/// 金库未开启白名单时直接通过；开启后必须传入该金库的白名单账户，
+/// 且接收者在当前时刻已生效、未被移除。
+fn ensure_receiver_allowed(
+    vault: &Account<Vault>,
+    allowlist: Option<&ReceiverAllowlist>,
+    receiver: &Pubkey,
+) -> Result<()> {
+    if !vault.receiver_allowlist {
+        return Ok(());
+    }
+
+    let allowlist = allowlist.ok_or(VaultError::ReceiverNotAllowed)?;
+    require_keys_eq!(allowlist.vault, vault.key(), VaultError::ReceiverNotAllowed);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(allowlist.is_allowed(receiver, now), VaultError::ReceiverNotAllowed);
+
+    Ok(())
+}
+
+/// 写入操作历史
+///
+/// 金库没有开启操作历史时直接返回；开启后必须传入该金库的历史账户。
+fn record_history(
+    vault: &Account<Vault>,
+    history: Option<&mut VaultHistory>,
+    kind: HistoryKind,
+    actor: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    if !vault.history_enabled {
+        return Ok(());
+    }
+
+    let history = history.ok_or(VaultError::HistoryAccountRequired)?;
+    require_keys_eq!(history.vault, vault.key(), VaultError::HistoryAccountRequired);
+
+    history.push(HistoryEntry {
+        kind,
+        actor,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// 所有者提款
+///
+/// `withdraw`、`withdraw_all`、`withdraw_bps` 共用，负责：
+/// - 多签模式检查
+/// - 接收者白名单检查
+/// - 转账（金额、时间锁、提款限额、手续费、租金豁免检查）
+/// - 事件、操作历史和日志
+///
+/// 返回接收者实际收到的金额（扣除手续费后）
+fn process_withdraw(accounts: &mut Withdraw, amount: u64) -> Result<u64> {
+    // 验证：多签模式下必须通过提案提款
+    require!(accounts.vault.multisig.is_none(), VaultError::MultisigRequired);
+
+    // 验证：接收者白名单（如果开启了）
+    let receiver = accounts.receiver.to_account_info();
+    ensure_receiver_allowed(&accounts.vault, accounts.allowlist.as_deref(), receiver.key)?;
+
+    // 执行转账（金额、时间锁、手续费、租金豁免检查都在辅助函数中完成）
+    let fee = withdraw_lamports(
+        &mut accounts.vault,
+        &receiver,
+        amount,
+        &accounts.config,
+        accounts.treasury.as_deref(),
+    )?;
+    record_heartbeat(&mut accounts.vault)?;
+
+    let vault = &accounts.vault;
+    let received = amount - fee.amount;
+    emit_withdrawn(vault, accounts.authority.key(), receiver.key(), received, fee.amount)?;
+
+    // 写入操作历史（如果开启了，金额含手续费，见 `HistoryEntry::amount`）
+    record_history(
+        vault,
+        accounts.history.as_deref_mut(),
+        HistoryKind::Withdraw,
+        accounts.authority.key(),
+        amount,
+    )?;
+
+    // 获取提款后的剩余余额（用于日志）
+    let remaining_balance = vault.get_lamports();
+
+    // 记录日志（与 `Withdrawn` 事件一致：接收者收到的金额和手续费分开记录）
+    msg!("💸 Withdrew {} lamports", received);
+    log_fee(&fee);
+    msg!("   Total withdrawals: {}", vault.total_withdrawals);
+    msg!("   Remaining balance: {}", remaining_balance);
+
+    Ok(received)
+}
+
+/// 计算金库的可用余额
+///
+/// 可用余额 = 总余额 − 租金豁免最低余额（不包括质押中的 SOL）
+fn available_balance(vault: &Account<Vault>) -> Result<u64> {
+    // 计算租金豁免所需的最低余额
+    // 在 Solana 中，账户必须保留足够的余额才能免除租金
+    let rent = Rent::get()?;
+    let min_balance = rent.minimum_balance(vault.to_account_info().data_len());
+
+    // saturating_sub 确保不会下溢（结果最小为 0）
+    Ok(vault.get_lamports().saturating_sub(min_balance))
+}
+
+/// 从金库转出 SOL（不做权限和时间锁检查）
+///
+/// - 保留租金豁免最低余额
+/// - 直接修改 lamports
+/// - 更新总提款统计
+fn transfer_from_vault(vault: &mut Account<Vault>, receiver: &AccountInfo, amount: u64) -> Result<()> {
+    // 计算可用余额 = 总余额 - 最低保留余额
+    let available_balance = available_balance(vault)?;
+
+    // 验证：可用余额必须大于等于提款金额
+    // 质押中的 SOL 不在金库账户中，需要先 deactivate_stake + withdraw_stake
+    if available_balance < amount && vault.staked_lamports > 0 {
+        return err!(VaultError::FundsStaked);
+    }
+    require!(available_balance >= amount, VaultError::InsufficientFunds);
+
+    // 直接修改账户的 lamports（底层操作）
+    // 从金库减少 amount
+    **vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+    // 给接收者增加 amount
+    **receiver.try_borrow_mut_lamports()? += amount;
+
+    // 使用 checked_add 安全地累加，防止溢出
+    vault.total_withdrawals = vault.total_withdrawals.checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+
+    Ok(())
+}
+
+/// 从金库代币账户提取代币
+///
+/// `withdraw_token` 和多签代币提款共用，负责：
+/// - 数量必须大于 0
+/// - 时间锁（按该 mint 的累计存取款计算已解锁部分）
+/// - 金库代币余额检查
+/// - 由金库 PDA 签名转账并更新该 mint 的总提款统计
+fn withdraw_tokens<'info>(
+    vault: &Account<'info, Vault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    token_totals: &mut Account<'info, TokenTotals>,
+    receiver_token_account: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    // 验证：提款数量必须大于 0
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    // 验证：时间锁（按该 mint 的累计存取款计算已解锁部分）
+    if let Some(lock) = &vault.lock {
+        let now = Clock::get()?.unix_timestamp;
+        let unlocked = lock.unlocked_amount(
+            token_totals.total_deposits,
+            token_totals.total_withdrawals,
+            now,
+        );
+        require!(amount <= unlocked, VaultError::StillLocked);
+    }
+
+    // 验证：金库代币余额必须足够
+    require!(vault_token_account.amount >= amount, VaultError::InsufficientFunds);
+
+    // 金库 PDA 的签名 seeds（使用存储的 bump）
+    let seeds = &[
+        b"vault".as_ref(),
+        vault.original_authority.as_ref(),
+        vault.vault_name.as_bytes(),
+        &[vault.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault_token_account.to_account_info(),
+                to: receiver_token_account.to_account_info(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    // 更新该 mint 的总提款统计
+    token_totals.total_withdrawals = token_totals.total_withdrawals.checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+
+    // 重新加载金库代币账户以获取最新余额（用于日志记录）
+    vault_token_account.reload()?;
+
+    Ok(())
+}
+
+/// 关闭金库时一起关闭操作历史
+///
+/// `close_vault`、`execute_close` 共用。金库没有开启操作历史时直接返回；
+/// 开启后必须传入该金库的历史账户，租金转给 `destination`。
+fn close_history<'info>(
+    vault: &Account<Vault>,
+    history: Option<&Account<'info, VaultHistory>>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    if !vault.history_enabled {
+        return Ok(());
+    }
+
+    let history = history.ok_or(VaultError::HistoryAccountRequired)?;
+    require_keys_eq!(history.vault, vault.key(), VaultError::HistoryAccountRequired);
+    history.close(destination.clone())
+}
+
+/// 关闭金库时一起关闭接收者白名单
+///
+/// `close_vault`、`execute_close` 共用。否则白名单的租金无法取回，
+/// 同名金库重新创建后 `enable_allowlist` 也会因账户已存在而失败。
+fn close_allowlist<'info>(
+    vault: &Account<Vault>,
+    allowlist: Option<&Account<'info, ReceiverAllowlist>>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    if !vault.receiver_allowlist {
+        return Ok(());
+    }
+
+    let allowlist = allowlist.ok_or(VaultError::AllowlistAccountRequired)?;
+    require_keys_eq!(allowlist.vault, vault.key(), VaultError::AllowlistAccountRequired);
+    allowlist.close(destination.clone())
+}
+
+/// 提案账户关闭后减少未完成的提案数量
+///
+/// 版本 3 之前的金库没有该字段，直接跳过；迁移前创建的提案没有计入，用 saturating_sub 避免下溢
+fn release_proposal(vault: &mut Vault) {
+    if vault.has_fields(VAULT_VERSION_LINKED_ACCOUNTS) {
+        vault.open_proposals = vault.open_proposals.saturating_sub(1);
+    }
+}
+
+/// 检查金库是否可以关闭
+///
+/// - 时间锁完全到期前不能关闭，否则可以绕过时间锁
+/// - 还有质押或关联账户时不能关闭，否则这些资金会失去追踪
+/// - 还有提案或（可退款模式下）未退款的存款回执时不能关闭，
+///   否则同名金库重新创建后会沿用这些账户
+fn ensure_closable(vault: &Vault) -> Result<()> {
+    // 质押中的 SOL 不在金库账户中，关闭前必须先取回
+    require!(vault.staked_lamports == 0, VaultError::FundsStaked);
+
+    // 关联账户（委托、支付流、代币账户）依赖金库 PDA，关闭后会失去对它们的控制
+    if vault.open_delegates > 0 || vault.open_streams > 0 || vault.open_token_accounts > 0 {
+        msg!(
+            "Linked accounts still open: {} delegates, {} streams, {} token accounts",
+            vault.open_delegates,
+            vault.open_streams,
+            vault.open_token_accounts
+        );
+        return err!(VaultError::LinkedAccountsOpen);
+    }
+
+    // 提案的 PDA 由金库地址和编号派生，遗留的提案会占用重新创建的金库的编号
+    if vault.open_proposals > 0 {
+        msg!("Proposals still open: {}", vault.open_proposals);
+        return err!(VaultError::LinkedAccountsOpen);
+    }
+
+    // 存款人仍可以通过回执退款
+    if vault.refundable && vault.receipt_balance > 0 {
+        msg!("Refundable deposits outstanding: {} lamports", vault.receipt_balance);
+        return err!(VaultError::LinkedAccountsOpen);
+    }
+    if let Some(lock) = &vault.lock {
+        let now = Clock::get()?.unix_timestamp;
+        require!(lock.is_fully_unlocked(now), VaultError::StillLocked);
+    }
+    Ok(())
+}
+
+/// 检查提案是否可以执行
+///
+/// - 金库处于多签模式
+/// - 执行者是多签成员
+/// - 提案属于当前的多签配置（`multisig_nonce` 一致）
+/// - 仍是多签成员的批准数达到阈值
+fn check_proposal_ready(vault: &Vault, proposal: &Proposal, executor: &Signer) -> Result<()> {
+    let multisig = vault.multisig.as_ref().ok_or(VaultError::MultisigNotEnabled)?;
+
+    require!(multisig.is_signer(&executor.key()), VaultError::NotMultisigSigner);
+    require!(
+        proposal.multisig_nonce == vault.multisig_nonce,
+        VaultError::StaleProposal
+    );
+
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|approver| multisig.is_signer(approver))
+        .count();
+    require!(
+        approvals >= multisig.threshold as usize,
+        VaultError::NotEnoughApprovals
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// 账户验证结构
+// ============================================================================
+// 以下结构定义了每个指令需要的账户及其约束条件
+// Anchor 会在运行时自动验证这些约束
+
+/// 初始化金库指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 要创建的金库 PDA 账户
+/// - `registry`: 所有者的金库登记表（PDA: `[b"registry", authority]`，首次创建金库时创建）
+/// - `authority`: 金库所有者（签名者，支付创建费用）
+/// - `system_program`: Solana 系统程序（用于创建账户）
+#[derive(Accounts)]
+#[instruction(vault_name: String)]  // 声明指令参数，用于约束中
+pub struct Initialize<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        init,                           // 初始化新账户
+        payer = authority,              // 由 authority 支付账户创建费用
+        space = 8 + Vault::INIT_SPACE,  // 账户空间：8 字节判别器 + 数据大小
+        seeds = [                       // PDA seeds（用于派生地址）
+            b"vault",                   // 固定前缀
+            authority.key().as_ref(),   // 所有者公钥
+            vault_name.as_bytes()       // 金库名称
+        ],
+        bump                            // PDA bump seed（Anchor 自动查找）
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库登记表（PDA，首次创建金库时在指令中创建，之后每次创建金库时扩容）
+    ///
+    /// 不使用 `init_if_needed`：登记表的大小随登记数量变化，
+    /// 而 `init_if_needed` 要求已存在的账户大小等于 `space`，第二个金库就会创建失败。
+    /// CHECK: seeds 约束保证地址正确；所有者和判别器在 `register_vault` 中检查
+    #[account(
+        mut,
+        seeds = [b"registry", authority.key().as_ref()],
+        bump
+    )]
+    pub registry: UncheckedAccount<'info>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]  // mut: 因为要扣除创建账户的费用
+    pub authority: Signer<'info>,
+
+    /// Solana 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 由单独付款人创建金库的账户验证
+///
+/// # 账户说明
+/// - `vault`: 要创建的金库 PDA 账户
+/// - `registry`: 所有者的金库登记表（PDA: `[b"registry", authority]`）
+/// - `authority`: 金库所有者（签名者，可以是其他程序通过 `invoke_signed` 签名的 PDA）
+/// - `payer`: 支付创建费用的账户（签名者）
+/// - `system_program`: Solana 系统程序（用于创建账户）
+#[derive(Accounts)]
+#[instruction(vault_name: String)]
+pub struct InitializeWithPayer<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", authority.key().as_ref(), vault_name.as_bytes()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库登记表（PDA，首次创建金库时在指令中创建，见 `Initialize::registry`）
+    /// CHECK: seeds 约束保证地址正确；所有者和判别器在 `register_vault` 中检查
+    #[account(
+        mut,
+        seeds = [b"registry", authority.key().as_ref()],
+        bump
+    )]
+    pub registry: UncheckedAccount<'info>,
+
+    /// 金库所有者（必须签名，不需要持有 SOL）
+    pub authority: Signer<'info>,
+
+    /// 付款人（必须签名，支付租金）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Solana 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 存款指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（接收存款）
+/// - `receipt`: 存款人的存款回执（PDA: `[b"receipt", vault, depositor]`，仅在开启可退款模式后需要）
+/// - `depositor`: 存款人（签名者，支付存款）
+/// - `system_program`: 系统程序（用于转账）
+/// - `history`: 操作历史（可选，不传时这笔存款不写入历史）
+///
+/// # 权限
+/// - 任何人都可以向金库存款，不需要是所有者
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    /// 金库账户（PDA，可变因为余额会增加）
+    #[account(
+        mut,                            // 可变：余额和统计数据会更新
+        seeds = [                       // 验证 PDA
+            b"vault",
+            vault.original_authority.as_ref(),  // 使用原始创建者（不变）
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump               // 使用存储的 bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 存款回执（可选，PDA，首次传入时自动创建）
+    #[account(
+        init_if_needed,                 // 不存在时创建，存在时直接使用
+        payer = depositor,              // 由存款人支付回执租金
+        space = 8 + DepositorReceipt::INIT_SPACE,
+        seeds = [b"receipt", vault.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub receipt: Option<Account<'info, DepositorReceipt>>,
+
+    /// 存款人（必须签名）
+    #[account(mut)]  // mut: 因为要扣除存款金额
+    pub depositor: Signer<'info>,
+
+    /// 系统程序（用于 SOL 转账）
+    pub system_program: Program<'info, System>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+}
+
+/// 提款指令的账户验证
+///
+/// `withdraw`、`withdraw_all`、`withdraw_bps` 共用
+///
+/// # 账户说明
+/// - `vault`: 金库账户（发送提款）
+/// - `authority`: 金库所有者（必须签名）
+/// - `receiver`: 接收者（开启白名单后必须在白名单中）
+/// - `allowlist`: 接收者白名单（仅在开启白名单后需要）
+/// - `history`: 操作历史（仅在开启后需要）
+/// - `system_program`: 系统程序
+/// - `config`: 全局配置（PDA: `[b"config"]`，还没有创建时不收取手续费）
+/// - `treasury`: 协议金库（收取手续费时需要，必须与配置一致）
+///
+/// # 权限
+/// - **只有所有者**可以提款（通过 has_one 约束）
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    /// 金库账户（PDA，可变因为余额会减少）
+    #[account(
+        mut,                            // 可变：余额和统计数据会更新
+        has_one = authority,            // 验证：vault.authority 必须等于 authority 账户
+        seeds = [                       // 验证 PDA
+            b"vault",
+            vault.original_authority.as_ref(),  // 使用原始创建者
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump               // 使用存储的 bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库所有者（必须签名才能提款）
+    pub authority: Signer<'info>,
+
+    /// 接收者（可以是任何账户，不需要签名）
+    /// CHECK: 此账户可以是任何地址，由所有者指定（开启白名单后在指令中验证）
+    #[account(mut)]  // mut: 因为会增加余额
+    pub receiver: AccountInfo<'info>,
+
+    /// 接收者白名单（可选，在指令中验证属于该金库）
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+
+    /// 系统程序（实际未使用，但保留以保持一致性）
+    pub system_program: Program<'info, System>,
+
+    /// 全局配置（地址固定，客户端不能绕过手续费）
+    /// CHECK: 地址由 seeds 验证；账户可能还没有创建，在指令中按所有者解码
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（收取手续费时需要）
+    /// CHECK: 在指令中验证必须等于 `config.treasury`
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+/// 所有权管理指令的账户验证
+///
+/// `transfer_authority`、`set_one_step_transfer`、`propose_authority`、
+/// `cancel_authority_transfer` 共用
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `authority`: 当前所有者（必须签名）
+/// - `history`: 操作历史（仅在开启后由 `transfer_authority` 使用）
+///
+/// # 权限
+/// - **只有当前所有者**可以转移所有权
+#[derive(Accounts)]
+pub struct TransferAuthority<'info> {
+    /// 金库账户（PDA，可变因为 authority 会改变）
+    #[account(
+        mut,                            // 可变：authority 字段会更新
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [                       // 验证 PDA
+            b"vault",
+            vault.original_authority.as_ref(),  // 使用原始创建者（永不改变）
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump               // 使用存储的 bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 当前所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+}
+
+/// 金库设置指令的账户验证
+///
+/// 所有者修改金库设置的指令共用：`enable_refunds`、`set_rate_limit`、`set_require_reference`、`set_deposit_limits`、
+/// `set_beneficiary`、`heartbeat`
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `authority`: 当前所有者（必须签名）
+///
+/// # 权限
+/// - **只有当前所有者**可以修改金库设置
+#[derive(Accounts)]
+pub struct UpdateVaultSettings<'info> {
+    /// 金库账户（PDA，可变因为设置字段会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 当前所有者（必须签名）
+    pub authority: Signer<'info>,
+}
+
+/// 退款指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（发送退款）
+/// - `receipt`: 存款人的存款回执
+/// - `depositor`: 存款人（必须签名，接收退款）
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    /// 金库账户（PDA，可变因为余额和统计会更新）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 存款回执（PDA，可变因为已退款金额会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = depositor,            // 验证：必须是存款人本人的回执
+        seeds = [b"receipt", vault.key().as_ref(), depositor.key().as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Account<'info, DepositorReceipt>,
+
+    /// 存款人（必须签名，接收退款）
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+}
+
+/// 迁移金库指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 要迁移的金库（在指令中扩容到当前布局）
+/// - `payer`: 支付额外租金的账户（必须签名）
+/// - `system_program`: 系统程序（用于转账租金）
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 支付额外租金的账户（必须签名）
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 系统程序（用于转账租金）
+    pub system_program: Program<'info, System>,
+}
+
+/// 设置守护者指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（guardian 会更新）
+/// - `authority`: 金库所有者（必须签名）
+/// - `current_guardian`: 当前守护者（已设置守护者时必须签名）
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 当前守护者（可选，在指令中验证）
+    pub current_guardian: Option<Signer<'info>>,
+}
+
+/// 暂停金库指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（paused 会更新）
+/// - `guardian`: 守护者（必须签名，在指令中验证）
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 守护者（必须签名）
+    pub guardian: Signer<'info>,
+}
+
+/// 解除暂停指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（paused 会更新）
+/// - `authority`: 金库所有者（必须签名）
+/// - `guardian`: 守护者（必须签名，在指令中验证）
+#[derive(Accounts)]
+pub struct Unpause<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 守护者（必须签名）
+    pub guardian: Signer<'info>,
+}
+
+/// 开启操作历史指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（history_enabled 会更新）
+/// - `history`: 要创建的历史账户（PDA: `[b"history", vault]`）
+/// - `authority`: 金库所有者（签名者，支付租金）
+#[derive(Accounts)]
+pub struct EnableHistory<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 历史账户（PDA，每个金库只能创建一次）
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultHistory::INIT_SPACE,
+        seeds = [b"history", vault.key().as_ref()],
+        bump
+    )]
+    pub history: Account<'info, VaultHistory>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 开启接收者白名单指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（receiver_allowlist 会更新）
+/// - `allowlist`: 要创建的白名单账户（PDA: `[b"allowlist", vault]`）
+/// - `authority`: 金库所有者（签名者，支付租金）
+#[derive(Accounts)]
+pub struct EnableAllowlist<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 白名单账户（PDA，每个金库只能创建一次）
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ReceiverAllowlist::INIT_SPACE,
+        seeds = [b"allowlist", vault.key().as_ref()],
+        bump
+    )]
+    pub allowlist: Account<'info, ReceiverAllowlist>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 增删白名单接收者指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `allowlist`: 白名单账户
+/// - `authority`: 金库所有者（必须签名）
+#[derive(Accounts)]
+pub struct ManageAllowlist<'info> {
+    /// 金库账户（PDA，可变因为会记录心跳）
+    #[account(
+        mut,                            // 可变：last_heartbeat 会更新
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 白名单账户（PDA，可变因为接收者列表会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"allowlist", vault.key().as_ref()],
+        bump = allowlist.bump
+    )]
+    pub allowlist: Account<'info, ReceiverAllowlist>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+}
+
+/// 授权委托人指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `delegate_account`: 要创建的委托账户（PDA: `[b"delegate", vault, delegate]`）
+/// - `delegate`: 被授权的委托人
+/// - `authority`: 金库所有者（签名者，支付租金）
+#[derive(Accounts)]
+pub struct GrantDelegate<'info> {
+    /// 金库账户（PDA，可变因为关联账户计数会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 委托账户（PDA）
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Delegate::INIT_SPACE,
+        seeds = [b"delegate", vault.key().as_ref(), delegate.key().as_ref()],
+        bump
+    )]
+    pub delegate_account: Account<'info, Delegate>,
+
+    /// 被授权的委托人
+    /// CHECK: 任意地址，只用于 PDA seeds 和记录
+    pub delegate: AccountInfo<'info>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 增加委托额度指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `delegate_account`: 委托账户
+/// - `authority`: 金库所有者（必须签名）
+#[derive(Accounts)]
+pub struct ManageDelegate<'info> {
+    /// 金库账户（PDA，可变因为会记录心跳）
+    #[account(
+        mut,                            // 可变：last_heartbeat 会更新
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 委托账户（PDA，可变因为额度会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"delegate", vault.key().as_ref(), delegate_account.delegate.as_ref()],
+        bump = delegate_account.bump
+    )]
+    pub delegate_account: Account<'info, Delegate>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+}
+
+/// 撤销委托指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `delegate_account`: 委托账户（将被关闭）
+/// - `authority`: 金库所有者（必须签名，接收租金）
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    /// 金库账户（PDA，可变因为关联账户计数会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 委托账户（PDA，将被关闭，租金退还给所有者）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        close = authority,
+        seeds = [b"delegate", vault.key().as_ref(), delegate_account.delegate.as_ref()],
+        bump = delegate_account.bump
+    )]
+    pub delegate_account: Account<'info, Delegate>,
+
+    /// 金库所有者（必须签名，接收租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// 委托人提款指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（发送提款）
+/// - `delegate_account`: 委托账户（额度会减少）
+/// - `delegate`: 委托人（必须签名）
+/// - `receiver`: 接收者（开启白名单后必须在白名单中）
+/// - `allowlist`: 接收者白名单（仅在开启白名单后需要）
+/// - `config`: 全局配置（PDA: `[b"config"]`，还没有创建时不收取手续费）
+/// - `treasury`: 协议金库（收取手续费时需要，必须与配置一致）
+///
+/// # 权限
+/// - **只有委托人**可以提款（通过 has_one 约束）
+#[derive(Accounts)]
+pub struct DelegateWithdraw<'info> {
+    /// 金库账户（PDA，可变因为余额和统计会更新）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 委托账户（PDA，可变因为额度会减少）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = delegate,             // 验证：必须是委托人本人
+        seeds = [b"delegate", vault.key().as_ref(), delegate.key().as_ref()],
+        bump = delegate_account.bump
+    )]
+    pub delegate_account: Account<'info, Delegate>,
+
+    /// 委托人（必须签名才能提款）
+    pub delegate: Signer<'info>,
+
+    /// 接收者（可以是任何账户，不需要签名）
+    /// CHECK: 此账户可以是任何地址，由委托人指定（开启白名单后在指令中验证）
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    /// 接收者白名单（可选，在指令中验证属于该金库）
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 全局配置（地址固定，客户端不能绕过手续费）
+    /// CHECK: 地址由 seeds 验证；账户可能还没有创建，在指令中按所有者解码
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（收取手续费时需要）
+    /// CHECK: 在指令中验证必须等于 `config.treasury`
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+/// 创建支付流指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（stream_count 会更新）
+/// - `stream`: 要创建的支付流（PDA: `[b"stream", vault, stream_id (u64 小端)]`）
+/// - `authority`: 金库所有者（签名者，支付租金）
+#[derive(Accounts)]
+#[instruction(stream_id: u64)]
+pub struct CreateStream<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 支付流账户（PDA）
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PaymentStream::INIT_SPACE,
+        seeds = [b"stream", vault.key().as_ref(), &stream_id.to_le_bytes()],
+        bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 领取支付流指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（发送款项）
+/// - `stream`: 支付流账户（claimed 会更新）
+/// - `recipient`: 收款人（必须签名，接收款项）
+/// - `allowlist`: 接收者白名单（仅在开启白名单后需要）
+/// - `config`: 全局配置（PDA: `[b"config"]`，还没有创建时不收取手续费）
+/// - `treasury`: 协议金库（收取手续费时需要，必须与配置一致）
+#[derive(Accounts)]
+pub struct ClaimStream<'info> {
+    /// 金库账户（PDA，可变因为余额和统计会更新）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 支付流账户（PDA）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = recipient,            // 验证：必须是收款人本人
+        seeds = [b"stream", vault.key().as_ref(), &stream.id.to_le_bytes()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+
+    /// 收款人（必须签名）
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    /// 接收者白名单（可选，在指令中验证属于该金库）
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 全局配置（地址固定，客户端不能绕过手续费）
+    /// CHECK: 地址由 seeds 验证；账户可能还没有创建，在指令中按所有者解码
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（收取手续费时需要）
+    /// CHECK: 在指令中验证必须等于 `config.treasury`
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+/// 取消支付流指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（结算时发送款项）
+/// - `stream`: 支付流账户（将被关闭）
+/// - `recipient`: 收款人（接收结算款项，不需要签名）
+/// - `authority`: 金库所有者（必须签名，接收租金）
+/// - `allowlist`: 接收者白名单（仅在开启白名单后需要）
+/// - `config`: 全局配置（PDA: `[b"config"]`，还没有创建时不收取手续费）
+/// - `treasury`: 协议金库（收取手续费时需要，必须与配置一致）
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 支付流账户（PDA，将被关闭，租金退还给所有者）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = recipient,            // 验证：收款人必须匹配
+        close = authority,
+        seeds = [b"stream", vault.key().as_ref(), &stream.id.to_le_bytes()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+
+    /// 收款人
+    /// CHECK: 通过 stream 的 has_one = recipient 约束验证
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// 金库所有者（必须签名，接收租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 接收者白名单（可选，在指令中验证属于该金库）
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 全局配置（地址固定，客户端不能绕过手续费）
+    /// CHECK: 地址由 seeds 验证；账户可能还没有创建，在指令中按所有者解码
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（收取手续费时需要）
+    /// CHECK: 在指令中验证必须等于 `config.treasury`
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+/// 质押闲置 SOL 指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（转出质押本金，作为 staker / withdrawer）
+/// - `stake_account`: 质押账户（PDA，由本指令创建）
+/// - `vote_account`: 要委托的验证者投票账户
+/// - `authority`: 金库所有者（必须签名）
+/// - `stake_program`、`system_program`: 创建和委托质押账户
+/// - `rent`、`clock`、`stake_history`、`stake_config`: 质押程序需要的系统账户
+#[derive(Accounts)]
+pub struct StakeIdle<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 质押账户（PDA，staker / withdrawer 都是金库）
+    /// CHECK: 地址由 seeds 约束验证，内容由质押程序验证
+    #[account(
+        mut,
+        seeds = [b"stake", vault.key().as_ref()],
+        bump
+    )]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// 验证者投票账户
+    /// CHECK: 由质押程序在 delegate_stake 中验证
+    pub vote_account: UncheckedAccount<'info>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 质押程序
+    /// CHECK: 通过 address 约束验证
+    #[account(address = stake_program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// 系统程序（分配质押账户空间）
+    pub system_program: Program<'info, System>,
+
+    /// 租金 sysvar
+    pub rent: Sysvar<'info, Rent>,
+
+    /// 时钟 sysvar
+    pub clock: Sysvar<'info, Clock>,
+
+    /// 质押历史 sysvar
+    /// CHECK: 通过 address 约束验证
+    #[account(address = stake_history::ID)]
+    pub stake_history: UncheckedAccount<'info>,
+
+    /// 质押配置账户
+    /// CHECK: 通过 address 约束验证
+    #[account(address = STAKE_CONFIG_ID)]
+    pub stake_config: UncheckedAccount<'info>,
+}
+
+/// 解除质押指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（作为 staker 签名）
+/// - `stake_account`: 质押账户（PDA）
+/// - `authority`: 金库所有者（必须签名）
+#[derive(Accounts)]
+pub struct DeactivateStake<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 质押账户（PDA，staker / withdrawer 都是金库）
+    /// CHECK: 地址由 seeds 约束验证，内容由质押程序验证
+    #[account(
+        mut,
+        seeds = [b"stake", vault.key().as_ref()],
+        bump
+    )]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 质押程序
+    /// CHECK: 通过 address 约束验证
+    #[account(address = stake_program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// 时钟 sysvar
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/// 取回质押指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（作为 withdrawer 签名，接收取回的 SOL）
+/// - `stake_account`: 质押账户（PDA，余额全部取回后关闭）
+/// - `authority`: 金库所有者（必须签名）
+#[derive(Accounts)]
+pub struct WithdrawStake<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 质押账户（PDA，staker / withdrawer 都是金库）
+    /// CHECK: 地址由 seeds 约束验证，内容由质押程序验证
+    #[account(
+        mut,
+        seeds = [b"stake", vault.key().as_ref()],
+        bump
+    )]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// 金库所有者（必须签名）
+    pub authority: Signer<'info>,
+
+    /// 质押程序
+    /// CHECK: 通过 address 约束验证
+    #[account(address = stake_program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// 时钟 sysvar
+    pub clock: Sysvar<'info, Clock>,
+
+    /// 质押历史 sysvar
+    /// CHECK: 通过 address 约束验证
+    #[account(address = stake_history::ID)]
+    pub stake_history: UncheckedAccount<'info>,
+}
+
+/// 接受所有权指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `new_authority`: 待接受的新所有者（必须签名）
+///
+/// # 权限
+/// - 只有 `pending_authority` 本人可以接受（在指令中验证）
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    /// 金库账户（PDA，可变因为 authority 会改变）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 待接受的新所有者（必须签名）
+    pub new_authority: Signer<'info>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+}
+
+/// 受益人领取金库指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `beneficiary`: 受益人（必须签名）
+///
+/// # 权限
+/// - 只有 `beneficiary` 本人可以领取，且所有者已超过不活跃期限（在指令中验证）
+#[derive(Accounts)]
+pub struct ClaimInheritance<'info> {
+    /// 金库账户（PDA，可变因为 authority 会改变）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 受益人（必须签名）
+    pub beneficiary: Signer<'info>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+}
+
+/// 关闭金库指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（将被关闭）
+/// - `authority`: 金库所有者（未指定 destination 时接收剩余余额）
+/// - `destination`: 可选的接收剩余余额的账户
+/// - `history`: 操作历史（仅在开启后需要，将被关闭）
+/// - `allowlist`: 接收者白名单（仅在开启后需要，将被关闭）
+/// - `registry`: 创建者的金库登记表（PDA: `[b"registry", original_authority]`，已创建时移除该金库）
+///
+/// # 权限
+/// - **只有当前所有者**可以关闭金库
+///
+/// # 效果
+/// - 金库账户被删除
+/// - 所有余额转给 destination（未指定时转给所有者）
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    /// 金库账户（PDA，将被关闭）
+    #[account(
+        mut,                            // 可变：账户会被关闭
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [                       // 验证 PDA
+            b"vault",
+            vault.original_authority.as_ref(),  // 使用原始创建者
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump               // 使用存储的 bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库所有者（必须签名，未指定 destination 时接收剩余余额）
+    #[account(mut)]  // mut: 因为可能接收余额
+    pub authority: Signer<'info>,
+
+    /// 接收剩余余额的账户（可选）
+    /// CHECK: 任意地址，只接收 lamports
+    #[account(mut)]
+    pub destination: Option<UncheckedAccount<'info>>,
+
+    /// 操作历史（开启后需要，与金库一起关闭）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+    /// 接收者白名单（开启后需要，与金库一起关闭）
+    #[account(mut)]
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 创建者的金库登记表（PDA，必须传入；已创建时移除该金库并缩容）
+    /// CHECK: 通过 seeds 验证地址，在指令中按所有者判断是否已创建
+    #[account(
+        mut,
+        seeds = [b"registry", vault.original_authority.as_ref()],
+        bump
+    )]
+    pub registry: UncheckedAccount<'info>,
+}
+
+/// 开通代币托管指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `mint`: 要托管的代币 mint
+/// - `vault_token_account`: 金库代币账户（PDA，权限为金库 PDA）
+/// - `token_totals`: 该 mint 的存取款统计账户（PDA）
+/// - `authority`: 金库所有者（签名者，支付创建费用）
+///
+/// # PDA Seeds
+/// - 金库代币账户: `[b"vault_token", vault, mint]`
+/// - 统计账户: `[b"token_totals", vault, mint]`
+#[derive(Accounts)]
+pub struct InitTokenAccount<'info> {
+    /// 金库账户（PDA，可变因为关联账户计数会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 要托管的代币 mint
+    pub mint: Account<'info, Mint>,
+
+    /// 金库代币账户（PDA，由金库 PDA 控制）
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"vault_token", vault.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,       // 代币账户权限为金库 PDA
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// 该 mint 的存取款统计账户（PDA）
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenTotals::INIT_SPACE,
+        seeds = [b"token_totals", vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_totals: Account<'info, TokenTotals>,
+
+    /// 金库所有者（必须签名，支付租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+
+    /// SPL Token 程序（用于初始化代币账户）
+    pub token_program: Program<'info, Token>,
+}
+
+/// 代币存款指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `vault_token_account`: 金库代币账户（接收代币）
+/// - `token_totals`: 该 mint 的统计账户
+/// - `depositor_token_account`: 存款人的代币账户（同一 mint）
+/// - `depositor`: 存款人（签名者）
+///
+/// # 权限
+/// - 任何人都可以向金库存入代币
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库代币账户（PDA，可变因为余额会增加）
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// 该 mint 的统计账户（PDA，可变因为统计会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"token_totals", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump = token_totals.bump
+    )]
+    pub token_totals: Account<'info, TokenTotals>,
+
+    /// 存款人的代币账户（必须是同一 mint，且由存款人控制）
+    #[account(
+        mut,
+        token::mint = token_totals.mint,
+        token::authority = depositor,
+    )]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+
+    /// 存款人（必须签名）
+    pub depositor: Signer<'info>,
+
+    /// SPL Token 程序（用于代币转账）
+    pub token_program: Program<'info, Token>,
+}
+
+/// 代币提款指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（作为代币账户权限签名）
+/// - `vault_token_account`: 金库代币账户（发送代币）
+/// - `token_totals`: 该 mint 的统计账户
+/// - `receiver_token_account`: 接收者的代币账户（同一 mint，可以属于任何人）
+/// - `authority`: 金库所有者（必须签名）
+///
+/// # 权限
+/// - **只有所有者**可以提款（通过 has_one 约束）
+#[derive(Accounts)]
+pub struct WithdrawToken<'info> {
+    /// 金库账户（PDA，可变因为会记录心跳）
+    #[account(
+        mut,                            // 可变：last_heartbeat 会更新
+        has_one = authority,            // 验证：vault.authority 必须等于 authority 账户
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库代币账户（PDA，可变因为余额会减少）
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// 该 mint 的统计账户（PDA，可变因为统计会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"token_totals", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump = token_totals.bump
+    )]
+    pub token_totals: Account<'info, TokenTotals>,
+
+    /// 接收者的代币账户（必须是同一 mint）
+    #[account(
+        mut,
+        token::mint = token_totals.mint,
+    )]
+    pub receiver_token_account: Account<'info, TokenAccount>,
+
+    /// 金库所有者（必须签名才能提款）
+    pub authority: Signer<'info>,
+
+    /// SPL Token 程序（用于代币转账）
+    pub token_program: Program<'info, Token>,
+}
+
+/// 关闭代币托管指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（作为代币账户权限签名）
+/// - `vault_token_account`: 金库代币账户（将被关闭）
+/// - `token_totals`: 该 mint 的统计账户（将被关闭）
+/// - `authority`: 金库所有者（必须签名，接收租金）
+///
+/// # 权限
+/// - **只有所有者**可以关闭（通过 has_one 约束）
+#[derive(Accounts)]
+pub struct CloseTokenAccount<'info> {
+    /// 金库账户（PDA，可变因为关联账户计数会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库代币账户（PDA，将通过 Token 程序关闭）
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// 该 mint 的统计账户（PDA，将被关闭，租金退还给所有者）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        close = authority,
+        seeds = [b"token_totals", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump = token_totals.bump
+    )]
+    pub token_totals: Account<'info, TokenTotals>,
+
+    /// 金库所有者（必须签名，接收租金）
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// SPL Token 程序（用于关闭代币账户）
+    pub token_program: Program<'info, Token>,
+}
+
+/// 设置时间锁指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `authority`: 当前所有者（必须签名）
+///
+/// # 权限
+/// - **只有当前所有者**可以设置时间锁
+#[derive(Accounts)]
+pub struct SetLock<'info> {
+    /// 金库账户（PDA，可变因为 lock 字段会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 当前所有者（必须签名）
+    pub authority: Signer<'info>,
+}
+
+/// 启用多签指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `authority`: 当前所有者（必须签名）
+///
+/// # 权限
+/// - **只有当前所有者**可以启用多签
+#[derive(Accounts)]
+pub struct EnableMultisig<'info> {
+    /// 金库账户（PDA，可变因为 multisig 字段会更新）
+    #[account(
+        mut,
+        has_one = authority,            // 验证：必须是当前所有者
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 当前所有者（必须签名）
+    pub authority: Signer<'info>,
+}
+
+/// 创建提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（提案计数会增加）
+/// - `proposal`: 要创建的提案 PDA
+/// - `proposer`: 发起人（多签成员，签名并支付租金）
+///
+/// # PDA Seeds
+/// - `[b"proposal", vault, proposal_id (u64 小端)]`
+#[derive(Accounts)]
+#[instruction(proposal_id: u64)]
+pub struct Propose<'info> {
+    /// 金库账户（PDA，可变因为提案计数会增加）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA）
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal_id.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 发起人（必须签名，支付租金）
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// 批准提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户
+/// - `proposal`: 要批准的提案
+/// - `approver`: 批准人（多签成员，必须签名）
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    /// 金库账户（PDA）
+    #[account(
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，可变因为批准列表会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 批准人（必须签名）
+    pub approver: Signer<'info>,
+}
+
+/// 取消提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（提案计数会减少）
+/// - `proposal`: 要取消的提案（关闭）
+/// - `proposer`: 提案发起人（接收提案账户租金）
+/// - `closer`: 调用者（必须签名；提案未失效时必须是发起人）
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    /// 金库账户（PDA，可变因为提案计数会减少）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，关闭后租金退还给发起人）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = proposer,             // 验证：租金退还给发起人
+        close = proposer,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 提案发起人（接收提案账户租金）
+    /// CHECK: 通过 has_one = proposer 验证
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    /// 调用者（必须签名）
+    pub closer: Signer<'info>,
+}
+
+/// 执行提款提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（发送提款）
+/// - `proposal`: 要执行的提案（执行后关闭）
+/// - `proposer`: 提案发起人（接收提案账户租金）
+/// - `receiver`: 接收者（必须与提案一致）
+/// - `executor`: 执行人（多签成员，必须签名）
+/// - `config`: 全局配置（PDA: `[b"config"]`，还没有创建时不收取手续费）
+/// - `treasury`: 协议金库（收取手续费时需要，必须与配置一致）
+#[derive(Accounts)]
+pub struct ExecuteWithdrawal<'info> {
+    /// 金库账户（PDA，可变因为余额和统计会更新）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，执行后关闭，租金退还给发起人）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = proposer,             // 验证：租金退还给发起人
+        close = proposer,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 提案发起人（接收提案账户租金）
+    /// CHECK: 通过 has_one = proposer 验证
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    /// 接收者
+    /// CHECK: 在指令中验证必须与提案指定的接收者一致
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    /// 执行人（必须签名）
+    pub executor: Signer<'info>,
+
+    /// 全局配置（地址固定，客户端不能绕过手续费）
+    /// CHECK: 地址由 seeds 验证；账户可能还没有创建，在指令中按所有者解码
+    #[account(seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（收取手续费时需要）
+    /// CHECK: 在指令中验证必须等于 `config.treasury`
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+}
+
+/// 执行代币提款提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（作为代币账户权限签名）
+/// - `proposal`: 要执行的提案（执行后关闭）
+/// - `proposer`: 提案发起人（接收提案账户租金）
+/// - `vault_token_account`: 金库代币账户
+/// - `token_totals`: 该 mint 的统计账户
+/// - `receiver_token_account`: 接收者代币账户（必须与提案一致）
+/// - `executor`: 执行人（多签成员，必须签名）
+#[derive(Accounts)]
+pub struct ExecuteTokenWithdrawal<'info> {
+    /// 金库账户（PDA，可变因为提案计数会减少）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，执行后关闭，租金退还给发起人）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = proposer,             // 验证：租金退还给发起人
+        close = proposer,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 提案发起人（接收提案账户租金）
+    /// CHECK: 通过 has_one = proposer 验证
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    /// 金库代币账户（PDA，可变因为余额会减少）
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// 该 mint 的统计账户（PDA，可变因为统计会更新）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        seeds = [b"token_totals", vault.key().as_ref(), token_totals.mint.as_ref()],
+        bump = token_totals.bump
+    )]
+    pub token_totals: Account<'info, TokenTotals>,
+
+    /// 接收者的代币账户（必须是同一 mint）
+    #[account(
+        mut,
+        token::mint = token_totals.mint,
+    )]
+    pub receiver_token_account: Account<'info, TokenAccount>,
+
+    /// 执行人（必须签名）
+    pub executor: Signer<'info>,
+
+    /// SPL Token 程序（用于代币转账）
+    pub token_program: Program<'info, Token>,
+}
+
+/// 执行转移所有权提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（authority 会改变）
+/// - `proposal`: 要执行的提案（执行后关闭）
+/// - `proposer`: 提案发起人（接收提案账户租金）
+/// - `executor`: 执行人（多签成员，必须签名）
+/// - `history`: 操作历史（仅在开启后需要）
+#[derive(Accounts)]
+pub struct ExecuteAuthorityTransfer<'info> {
+    /// 金库账户（PDA，可变因为 authority 会改变）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，执行后关闭，租金退还给发起人）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = proposer,             // 验证：租金退还给发起人
+        close = proposer,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 提案发起人（接收提案账户租金）
+    /// CHECK: 通过 has_one = proposer 验证
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    /// 执行人（必须签名）
+    pub executor: Signer<'info>,
+
+    /// 操作历史（可选，在指令中验证属于该金库）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+}
+
+/// 执行关闭金库提案指令的账户验证
+///
+/// # 账户说明
+/// - `vault`: 金库账户（将被关闭）
+/// - `proposal`: 要执行的提案（执行后关闭）
+/// - `proposer`: 提案发起人（接收提案账户租金）
+/// - `destination`: 接收金库剩余余额的地址（必须与提案一致）
+/// - `executor`: 执行人（多签成员，必须签名）
+/// - `history`: 操作历史（仅在开启后需要，将被关闭）
+/// - `allowlist`: 接收者白名单（仅在开启后需要，将被关闭）
+/// - `registry`: 创建者的金库登记表（PDA: `[b"registry", original_authority]`，已创建时移除该金库）
+#[derive(Accounts)]
+pub struct ExecuteClose<'info> {
+    /// 金库账户（PDA，将被关闭）
+    #[account(
+        mut,
+        seeds = [
+            b"vault",
+            vault.original_authority.as_ref(),
+            vault.vault_name.as_bytes()
+        ],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// 提案账户（PDA，执行后关闭，租金退还给发起人）
+    #[account(
+        mut,
+        has_one = vault,                // 验证：必须属于该金库
+        has_one = proposer,             // 验证：租金退还给发起人
+        close = proposer,
+        seeds = [b"proposal", vault.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// 提案发起人（接收提案账户租金）
+    /// CHECK: 通过 has_one = proposer 验证
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    /// 接收金库剩余余额的地址
+    /// CHECK: 在指令中验证必须与提案指定的地址一致
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+
+    /// 执行人（必须签名）
+    pub executor: Signer<'info>,
+
+    /// 操作历史（开启后需要，与金库一起关闭）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+
+    /// 接收者白名单（开启后需要，与金库一起关闭）
+    #[account(mut)]
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 创建者的金库登记表（PDA，必须传入；已创建时移除该金库并缩容）
+    /// CHECK: 通过 seeds 验证地址，在指令中按所有者判断是否已创建
+    #[account(
+        mut,
+        seeds = [b"registry", vault.original_authority.as_ref()],
+        bump
+    )]
+    pub registry: UncheckedAccount<'info>,
+}
+
+/// 创建全局配置指令的账户验证
+///
+/// # 账户说明
+/// - `config`: 全局配置（PDA: `[b"config"]`）
+/// - `treasury`: 协议金库（接收手续费，必须已达到租金豁免最低余额）
+/// - `admin`: 配置管理员（必须是程序的升级权限，支付租金）
+/// - `program`: 本程序
+/// - `program_data`: 本程序的 ProgramData 账户（读取升级权限）
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    /// 全局配置（PDA，只能创建一次）
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + VaultConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// 协议金库
+    /// CHECK: 只记录地址，余额在 `validate_treasury` 中检查
+    pub treasury: UncheckedAccount<'info>,
+
+    /// 配置管理员（必须签名，支付租金）
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// 本程序（用于找到 ProgramData 地址）
+    pub program: Program<'info, crate::program::TokenVault>,
+
+    /// 本程序的 ProgramData（在指令中验证地址和升级权限）
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// 更新全局配置指令的账户验证（`set_withdraw_fee`）
+///
+/// # 账户说明
+/// - `config`: 全局配置
+/// - `admin`: 配置管理员（必须签名）
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// 全局配置（PDA）
+    #[account(
+        mut,
+        has_one = admin,                // 验证：必须是配置管理员
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// 配置管理员（必须签名）
+    pub admin: Signer<'info>,
+}
+
+/// 设置协议金库指令的账户验证
+///
+/// # 账户说明
+/// - `config`: 全局配置
+/// - `treasury`: 新的协议金库（必须已达到租金豁免最低余额）
+/// - `admin`: 配置管理员（必须签名）
+#[derive(Accounts)]
+pub struct SetTreasury<'info> {
+    /// 全局配置（PDA）
+    #[account(
+        mut,
+        has_one = admin,                // 验证：必须是配置管理员
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// 新的协议金库
+    /// CHECK: 只记录地址，余额在 `validate_treasury` 中检查
+    pub treasury: UncheckedAccount<'info>,
+
+    /// 配置管理员（必须签名）
+    pub admin: Signer<'info>,
+}
+
+// ============================================================================
+// 数据结构
+// ============================================================================
+
+/// 金库账户数据结构
+///
+/// # 字段说明
+/// - `authority`: 当前所有者（可以通过 accept_authority 等方式改变）
+/// - `original_authority`: 原始创建者（永不改变，用于 PDA 计算）
+/// - `vault_name`: 金库名称（用于标识和 PDA 计算）
+/// - `total_deposits`: 累计存款金额（只增不减）
+/// - `total_withdrawals`: 累计提款金额（只增不减）
+/// - `bump`: PDA bump seed（用于签名）
+/// - `lock`: 可选的提款时间锁
+/// - `multisig`: 可选的 M-of-N 多签配置
+/// - `multisig_nonce`: 当前多签配置的编号，提案记录发起时的编号
+/// - `pending_authority`: 两步转移中待接受的新所有者
+/// - `one_step_transfer`: 是否允许 `transfer_authority` 一步转移
+/// - `refundable`: 是否允许存款人取回自己的存款
+/// - `rate_limit`: 可选的提款限额
+/// - `receiver_allowlist`: 是否只允许向白名单中的接收者提款
+/// - `guardian`: 可以紧急暂停金库的守护者
+/// - `paused`: 是否已被暂停
+/// - `stream_count`: 已创建的支付流数量（下一个支付流的编号）
+/// - `version`: 账户布局版本（旧版 125 字节金库为 0）
+/// - `staked_lamports`: 转入质押账户的本金（不在金库账户中，不能直接提取）
+/// - `open_delegates` / `open_streams` / `open_token_accounts`: 尚未关闭的关联账户数量
+/// - `open_proposals`: 尚未执行或取消的提案数量
+/// - `receipt_balance`: 所有存款回执中尚未退款的存款总额
+/// - `history_enabled`: 是否在 `VaultHistory` 账户中记录操作历史
+/// - `require_reference`: 存款是否必须带参考号
+/// - `min_deposit` / `max_balance`: 存款限制（最低存款金额、余额上限）
+/// - `beneficiary` / `inactivity_period` / `last_heartbeat`: 继承设置和所有者最后一次操作的时间
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器（account discriminator）
+/// 32 字节  - authority (Pubkey)
+/// 32 字节  - original_authority (Pubkey)
+/// 36 字节  - vault_name (4 字节长度 + 最多 32 字节 UTF-8)
+/// 8 字节   - total_deposits (u64)
+/// 8 字节   - total_withdrawals (u64)
+/// 1 字节   - bump (u8)
+/// 26 字节  - lock (1 字节 Option + 1 字节枚举标签 + 最多 24 字节)
+/// 334 字节 - multisig (1 字节 Option + 4 + 10 × 32 + 1 + 8)
+/// 8 字节   - multisig_nonce (u64)
+/// 33 字节  - pending_authority (1 字节 Option + 32)
+/// 1 字节   - one_step_transfer (bool)
+/// 1 字节   - refundable (bool)
+/// 58 字节  - rate_limit (1 字节 Option + 32 + 25 字节 pending)
+/// 1 字节   - receiver_allowlist (bool)
+/// 33 字节  - guardian (1 字节 Option + 32)
+/// 1 字节   - paused (bool)
+/// 8 字节   - stream_count (u64)
+/// 1 字节   - version (u8)
+/// 8 字节   - staked_lamports (u64)
+/// 2 字节   - open_delegates (u16)
+/// 2 字节   - open_streams (u16)
+/// 2 字节   - open_token_accounts (u16)
+/// 2 字节   - open_proposals (u16)
+/// 8 字节   - receipt_balance (u64)
+/// 1 字节   - history_enabled (bool)
+/// 1 字节   - require_reference (bool)
+/// 8 字节   - min_deposit (u64)
+/// 9 字节   - max_balance (1 字节 Option + 8)
+/// 33 字节  - beneficiary (1 字节 Option + 32)
+/// 8 字节   - inactivity_period (i64)
+/// 8 字节   - last_heartbeat (i64)
+/// --------
+/// 722 字节 总计
+/// ```
+///
+/// # 版本
+/// - 版本 0：旧版布局（只有 `bump` 及之前的字段，共 125 字节），见 `LegacyVault`
+/// - 版本 1：`lock` ~ `version`（630 字节）
+/// - 版本 2：增加 `staked_lamports`（638 字节）
+/// - 版本 3：增加 `open_delegates` ~ `receipt_balance`（654 字节）
+/// - 版本 4：增加 `history_enabled`（655 字节）
+/// - 版本 5：增加 `require_reference`（656 字节）
+/// - 版本 6：增加 `min_deposit`、`max_balance`（673 字节）
+/// - 版本 7：增加 `beneficiary`、`inactivity_period`、`last_heartbeat`（722 字节，当前布局）
+///
+/// 所有版本都可以被 `Account<Vault>` 加载（见下方的 `AccountDeserialize` 实现）：
+/// 按 `version` 字段读取，账户比当前布局小时缺少的字节补 0，之后版本新增的字段取默认值；
+/// 写回时如果这些字段被修改则返回 `MigrationRequired`。
+///
+/// # 为什么需要 original_authority？
+/// - PDA 地址由 seeds 决定：[b"vault", authority, vault_name]
+/// - 如果使用 authority 作为 seed，转移所有权后地址会改变
+/// - 使用 original_authority 确保 PDA 地址永不改变
+/// - 这样所有权可以转移，但金库地址保持不变
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct Vault {
+    /// 当前所有者（可以被转移）
+    /// 拥有提款、转移所有权、关闭金库的权限
+    pub authority: Pubkey,
+
+    /// 原始创建者（永不改变）
+    /// 用于 PDA seeds 计算，确保金库地址不变
+    pub original_authority: Pubkey,
+
+    /// 金库名称（最多 32 字符）
+    /// 用于标识金库和 PDA seeds 计算
+    /// 同一用户可以创建多个不同名称的金库
+    #[max_len(32)]
+    pub vault_name: String,
+
+    /// 累计总存款金额（lamports）
+    /// 只会增加，记录历史存款总额
+    pub total_deposits: u64,
+
+    /// 累计总提款金额（lamports）
+    /// 只会增加，记录历史提款总额
+    pub total_withdrawals: u64,
+
+    /// PDA bump seed
+    /// 用于程序签名时生成有效的 PDA
+    pub bump: u8,
+
+    /// 提款时间锁（None 表示不加锁）
+    /// 只能通过 set_lock 收紧，不能移除
+    pub lock: Option<LockSchedule>,
+
+    /// 多签配置（None 表示单签模式）
+    /// 启用后提款、转移所有权、关闭金库都必须通过提案执行
+    pub multisig: Option<Multisig>,
+
+    /// 多签配置的编号（启用多签时写入当前 slot）
+    /// 提案记录发起时的编号，编号不一致的提案不能再被批准或执行
+    pub multisig_nonce: u64,
+
+    /// 两步转移中待接受的新所有者（None 表示没有进行中的转移）
+    pub pending_authority: Option<Pubkey>,
+
+    /// 是否允许 transfer_authority 一步转移（默认 false）
+    pub one_step_transfer: bool,
+
+    /// 是否允许存款人通过 refund 取回自己的存款（默认 false，开启后不能关闭）
+    pub refundable: bool,
+
+    /// 提款限额（None 表示不限额）
+    pub rate_limit: Option<RateLimit>,
+
+    /// 是否开启接收者白名单（默认 false，开启后不能关闭）
+    /// 白名单内容存放在 `ReceiverAllowlist` 账户中
+    pub receiver_allowlist: bool,
+
+    /// 守护者（None 表示没有守护者）
+    /// 可以暂停金库，解除暂停需要所有者共同签名
+    pub guardian: Option<Pubkey>,
+
+    /// 是否已被暂停（暂停期间除 unpause 外的指令都会失败）
+    pub paused: bool,
+
+    /// 已创建的支付流数量（下一个支付流的编号）
+    pub stream_count: u64,
+
+    /// 账户布局版本（见 `VAULT_VERSION`）
+    pub version: u8,
+
+    /// 转入质押账户的本金（lamports）
+    /// 这部分 SOL 不在金库账户中，需要先 deactivate_stake + withdraw_stake 才能使用
+    pub staked_lamports: u64,
+
+    /// 尚未撤销的委托数量（Delegate 账户）
+    pub open_delegates: u16,
+
+    /// 尚未取消的支付流数量（PaymentStream 账户）
+    pub open_streams: u16,
+
+    /// 尚未关闭的代币托管数量（金库代币账户）
+    /// 以上计数都为 0 时才能关闭金库
+    pub open_token_accounts: u16,
+
+    /// 尚未执行或取消的提案数量（Proposal 账户）
+    pub open_proposals: u16,
+
+    /// 所有存款回执中尚未退款的存款总额（lamports）
+    /// 可退款模式下不为 0 时不能关闭金库
+    pub receipt_balance: u64,
+    /// 是否记录操作历史（默认 false，开启后不能关闭）
+    /// 历史内容存放在 `VaultHistory` 账户中
+    pub history_enabled: bool,
+
+    /// 存款是否必须带参考号（默认 false，可通过 set_require_reference 修改）
+    pub require_reference: bool,
+
+    /// 单笔存款的最低金额（lamports，0 表示不限制）
+    pub min_deposit: u64,
+
+    /// 余额上限（lamports，不包括租金，None 表示不限制）
+    /// 达到上限后拒绝新的存款
+    pub max_balance: Option<u64>,
+
+    /// 受益人（None 表示没有设置继承）
+    /// 所有者超过 inactivity_period 没有操作后，可以通过 claim_inheritance 成为所有者
+    pub beneficiary: Option<Pubkey>,
+
+    /// 不活跃期限（秒）
+    pub inactivity_period: i64,
+
+    /// 所有者最后一次操作的时间（Unix 时间戳）
+    pub last_heartbeat: i64,
+}
+
+/// 质押配置账户地址（质押程序已不再使用，但 delegate_stake 仍要求传入）
+pub const STAKE_CONFIG_ID: Pubkey = pubkey!("StakeConfig11111111111111111111111111111111");
+
+/// 当前金库布局版本
+///
+/// 新字段只能追加在末尾，并且每次增加字段都要升级版本，同时在 `Vault::clear_fields_after`
+/// 中登记新字段：`try_deserialize` 按 `version` 忽略账户中不属于该版本布局的字节，
+/// 旧版本的金库在写回时拒绝修改新字段。
+pub const VAULT_VERSION: u8 = VAULT_VERSION_INHERITANCE;
+
+/// 版本 2：增加 `staked_lamports`
+pub const VAULT_VERSION_STAKING: u8 = 2;
+
+/// 版本 3：增加关联账户计数（`open_delegates` ~ `open_proposals`）和 `receipt_balance`
+pub const VAULT_VERSION_LINKED_ACCOUNTS: u8 = 3;
+
+/// 版本 4：增加 `history_enabled`
+pub const VAULT_VERSION_HISTORY: u8 = 4;
+
+/// 版本 5：增加 `require_reference`
+pub const VAULT_VERSION_REFERENCE: u8 = 5;
+
+/// 版本 6：增加 `min_deposit`、`max_balance`
+pub const VAULT_VERSION_DEPOSIT_LIMITS: u8 = 6;
+
+/// 版本 7：增加 `beneficiary`、`inactivity_period`、`last_heartbeat`
+pub const VAULT_VERSION_INHERITANCE: u8 = 7;
+
+impl Vault {
+    /// 金库的布局是否包含版本 `version` 新增的字段
+    ///
+    /// 旧版本的金库写回时不能修改之后版本新增的字段，更新这些字段的指令需要先检查
+    pub fn has_fields(&self, version: u8) -> bool {
+        self.version >= version
+    }
+
+    /// 将 `version` 之后的版本新增的字段恢复为默认值
+    ///
+    /// 这些字段不在该版本的账户布局中：读取时忽略账户中残留的字节，写回时不能修改
+    fn clear_fields_after(&mut self, version: u8) {
+        if version < VAULT_VERSION_INHERITANCE {
+            self.beneficiary = None;
+            self.inactivity_period = 0;
+            self.last_heartbeat = 0;
+        }
+        if version < VAULT_VERSION_DEPOSIT_LIMITS {
+            self.min_deposit = 0;
+            self.max_balance = None;
+        }
+        if version < VAULT_VERSION_REFERENCE {
+            self.require_reference = false;
+        }
+        if version < VAULT_VERSION_HISTORY {
+            self.history_enabled = false;
+        }
+        if version < VAULT_VERSION_LINKED_ACCOUNTS {
+            self.open_delegates = 0;
+            self.open_streams = 0;
+            self.open_token_accounts = 0;
+            self.open_proposals = 0;
+            self.receipt_balance = 0;
+        }
+        if version < VAULT_VERSION_STAKING {
+            self.staked_lamports = 0;
+        }
+        if version < 1 {
+            *self = Vault::from(LegacyVault::from(&*self));
+        }
+    }
+}
+
+/// 旧版金库布局版本（没有 version 字段）
+pub const LEGACY_VAULT_VERSION: u8 = 0;
+
+/// 旧版金库账户大小（8 字节判别器 + 117 字节数据）
+pub const LEGACY_VAULT_SIZE: usize = 125;
+
+/// 旧版金库布局（版本 0）
+///
+/// 只包含最初的字段，用于读写尚未迁移的金库。
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyVault {
+    /// 当前所有者
+    pub authority: Pubkey,
+
+    /// 原始创建者（用于 PDA seeds）
+    pub original_authority: Pubkey,
+
+    /// 金库名称
+    pub vault_name: String,
+
+    /// 累计总存款金额（lamports）
+    pub total_deposits: u64,
+
+    /// 累计总提款金额（lamports）
+    pub total_withdrawals: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl From<LegacyVault> for Vault {
+    fn from(legacy: LegacyVault) -> Self {
+        Vault {
+            authority: legacy.authority,
+            original_authority: legacy.original_authority,
+            vault_name: legacy.vault_name,
+            total_deposits: legacy.total_deposits,
+            total_withdrawals: legacy.total_withdrawals,
+            bump: legacy.bump,
+            lock: None,
+            multisig: None,
+            multisig_nonce: 0,
+            pending_authority: None,
+            one_step_transfer: false,
+            refundable: false,
+            rate_limit: None,
+            receiver_allowlist: false,
+            guardian: None,
+            paused: false,
+            stream_count: 0,
+            version: LEGACY_VAULT_VERSION,
+            staked_lamports: 0,
+            open_delegates: 0,
+            open_streams: 0,
+            open_token_accounts: 0,
+            open_proposals: 0,
+            receipt_balance: 0,
+            history_enabled: false,
+            require_reference: false,
+            min_deposit: 0,
+            max_balance: None,
+            beneficiary: None,
+            inactivity_period: 0,
+            last_heartbeat: 0,
+        }
+    }
+}
+
+impl From<&Vault> for LegacyVault {
+    fn from(vault: &Vault) -> Self {
+        LegacyVault {
+            authority: vault.authority,
+            original_authority: vault.original_authority,
+            vault_name: vault.vault_name.clone(),
+            total_deposits: vault.total_deposits,
+            total_withdrawals: vault.total_withdrawals,
+            bump: vault.bump,
+        }
+    }
+}
+
+// `Vault` 不使用 #[account]，而是手动实现账户 trait，以便同时读写两种版本的布局。
+// 判别器与 #[account] 生成的一致：sha256("account:Vault") 的前 8 字节。
+
+impl Discriminator for Vault {
+    const DISCRIMINATOR: &'static [u8] = &[211, 8, 232, 43, 2, 152, 117, 119];
+}
+
+impl Owner for Vault {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+impl AccountSerialize for Vault {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        if writer.write_all(Vault::DISCRIMINATOR).is_err() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+
+        // 旧版本的账户空间不足以保存之后版本的字段：这些字段被修改时必须先迁移
+        if self.version < VAULT_VERSION {
+            let mut expected = self.clone();
+            expected.clear_fields_after(self.version);
+            require!(expected == *self, VaultError::MigrationRequired);
+        }
+
+        let mut data = Vec::new();
+        if AnchorSerialize::serialize(self, &mut data).is_err() {
+            return Err(ErrorCode::AccountDidNotSerialize.into());
+        }
+
+        // 旧版本的账户比当前布局小：写满账户为止，写不下的只能是末尾取默认值的字段（全 0）
+        let mut written = 0;
+        while written < data.len() {
+            match writer.write(&data[written..]) {
+                Ok(0) => break,
+                Ok(n) => written += n,
+                Err(_) => return Err(ErrorCode::AccountDidNotSerialize.into()),
+            }
+        }
+        require!(
+            data[written..].iter().all(|byte| *byte == 0),
+            ErrorCode::AccountDidNotSerialize
+        );
+
+        Ok(())
+    }
+}
+
+impl AccountDeserialize for Vault {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < Vault::DISCRIMINATOR.len() {
+            return Err(ErrorCode::AccountDiscriminatorNotFound.into());
+        }
+        if &buf[..Vault::DISCRIMINATOR.len()] != Vault::DISCRIMINATOR {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch).with_account_name("Vault"));
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        // 旧版本的账户比当前布局小：缺少的字节补 0，即之后版本新增的字段取默认值
+        let mut data = buf[Vault::DISCRIMINATOR.len()..].to_vec();
+        if data.len() < Vault::INIT_SPACE {
+            data.resize(Vault::INIT_SPACE, 0);
+        }
+
+        let mut vault = Vault::deserialize(&mut data.as_slice())
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+
+        // 旧版本布局之后的字节可能是残留数据（之前写回的内容更长），按版本忽略
+        let version = vault.version;
+        vault.clear_fields_after(version);
+
+        Ok(vault)
+    }
+}
+
+/// 提款限额
+///
+/// 固定时间窗口计数：窗口到期后下一次提款开启新窗口并清零已用额度。
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct RateLimit {
+    /// 每个窗口最多可提取的金额（lamports）
+    pub max_per_window: u64,
+
+    /// 窗口长度（秒）
+    pub window_seconds: i64,
+
+    /// 当前窗口开始时间（Unix 时间戳，秒）
+    pub window_start: i64,
+
+    /// 当前窗口内已提取的金额（lamports）
+    pub spent_in_window: u64,
+
+    /// 排队中的放宽配置（到期后在下一次提款时生效）
+    pub pending: Option<RateLimitChange>,
+}
+
+/// 排队中的提款限额放宽
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct RateLimitChange {
+    /// 新的每窗口上限（lamports）
+    pub max_per_window: u64,
+
+    /// 新的窗口长度（秒）
+    pub window_seconds: i64,
+
+    /// 生效时间（Unix 时间戳，秒）
+    pub effective_ts: i64,
+}
+
+impl RateLimit {
+    /// 在 `now` 时刻提取 `amount`，超过限额时返回错误
+    ///
+    /// - 先应用已到期的放宽配置
+    /// - 窗口到期时开启新窗口
+    pub fn consume(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.advance(now);
+
+        let spent = self.spent_in_window.checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(spent <= self.max_per_window, VaultError::RateLimitExceeded);
+
+        self.spent_in_window = spent;
+
+        Ok(())
+    }
+
+    /// 在 `now` 时刻还可以提取的金额（不修改状态）
+    pub fn remaining(&self, now: i64) -> u64 {
+        let mut limit = *self;
+        limit.advance(now);
+        limit.max_per_window.saturating_sub(limit.spent_in_window)
+    }
+
+    /// 应用已到期的放宽配置，窗口到期时开启新窗口
+    fn advance(&mut self, now: i64) {
+        if let Some(change) = self.pending {
+            if now >= change.effective_ts {
+                self.max_per_window = change.max_per_window;
+                self.window_seconds = change.window_seconds;
+                self.pending = None;
+            }
+        }
+
+        if now >= self.window_start.saturating_add(self.window_seconds) {
+            self.window_start = now;
+            self.spent_in_window = 0;
+        }
+    }
+}
+
+/// 多签成员的最大数量
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+
+/// M-of-N 多签配置
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct Multisig {
+    /// 多签成员（最多 MAX_MULTISIG_SIGNERS 个）
+    #[max_len(10)]
+    pub signers: Vec<Pubkey>,
+
+    /// 执行提案所需的最少批准数
+    pub threshold: u8,
+
+    /// 已创建的提案数量（下一个提案的编号）
+    pub proposal_count: u64,
+}
+
+impl Multisig {
+    /// `key` 是否是多签成员
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+}
+
+/// 提款时间锁计划
+///
+/// # 变体说明
+/// - `Cliff`: 到 `unlock_ts` 之前完全锁定，之后全部解锁
+/// - `Linear`: `cliff_ts` 之前完全锁定，之后按 `start_ts` ~ `end_ts`
+///   的时间比例线性解锁，`end_ts` 之后全部解锁
+///
+/// 线性解锁的额度 = 累计存款 × 已过时间比例 − 累计提款，
+/// 因此锁定期间新存入的资金也按同一计划释放。
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum LockSchedule {
+    /// 到期一次性解锁
+    Cliff {
+        /// 解锁时间（Unix 时间戳，秒）
+        unlock_ts: i64,
+    },
+
+    /// 线性释放
+    Linear {
+        /// 释放开始时间（Unix 时间戳，秒）
+        start_ts: i64,
+        /// 悬崖时间，在此之前不可提款（Unix 时间戳，秒）
+        cliff_ts: i64,
+        /// 释放结束时间，之后全部解锁（Unix 时间戳，秒）
+        end_ts: i64,
+    },
+}
+
+/// 时间锁允许的最晚时间戳（9999-12-31 23:59:59 UTC）
+pub const MAX_LOCK_TIMESTAMP: i64 = 253_402_300_799;
+
+impl LockSchedule {
+    /// 时间参数是否合法
+    ///
+    /// 所有时间戳必须在 0 ~ `MAX_LOCK_TIMESTAMP` 之间，避免极端值在计算解锁额度时溢出
+    pub fn is_valid(&self) -> bool {
+        let in_range = |ts: i64| (0..=MAX_LOCK_TIMESTAMP).contains(&ts);
+        match *self {
+            LockSchedule::Cliff { unlock_ts } => in_range(unlock_ts),
+            LockSchedule::Linear { start_ts, cliff_ts, end_ts } => {
+                in_range(start_ts)
+                    && in_range(end_ts)
+                    && start_ts < end_ts
+                    && start_ts <= cliff_ts
+                    && cliff_ts <= end_ts
+            }
+        }
+    }
+
+    /// 开始有资金解锁的时间
+    fn first_unlock_ts(&self) -> i64 {
+        match *self {
+            LockSchedule::Cliff { unlock_ts } => unlock_ts,
+            LockSchedule::Linear { cliff_ts, .. } => cliff_ts,
+        }
+    }
+
+    /// 全部资金解锁的时间
+    fn full_unlock_ts(&self) -> i64 {
+        match *self {
+            LockSchedule::Cliff { unlock_ts } => unlock_ts,
+            LockSchedule::Linear { end_ts, .. } => end_ts,
+        }
+    }
+
+    /// 在 `now` 时刻是否已完全解锁
+    pub fn is_fully_unlocked(&self, now: i64) -> bool {
+        now >= self.full_unlock_ts()
+    }
+
+    /// 在 `now` 时刻还可以提取的数量
+    ///
+    /// - `deposited`: 累计存款
+    /// - `withdrawn`: 累计提款
+    pub fn unlocked_amount(&self, deposited: u64, withdrawn: u64, now: i64) -> u64 {
+        if now < self.first_unlock_ts() {
+            return 0;
+        }
+        if self.is_fully_unlocked(now) {
+            return u64::MAX;
+        }
+
+        // 只有 Linear 会走到这里（Cliff 的两个时间相同）
+        let vested = match *self {
+            LockSchedule::Linear { start_ts, end_ts, .. } => {
+                // 使用 i128 计算时间差：旧版本写入的极端时间戳相减会超出 i64
+                let elapsed = (now as i128 - start_ts as i128).max(0) as u128;
+                let duration = (end_ts as i128 - start_ts as i128) as u128;
+                (deposited as u128 * elapsed / duration) as u64
+            }
+            LockSchedule::Cliff { .. } => deposited,
+        };
+
+        vested.saturating_sub(withdrawn)
+    }
+
+    /// 新计划是否不比 `current` 宽松
+    ///
+    /// 判断标准：开始解锁时间和全部解锁时间都不早于旧计划；
+    /// 线性计划之间还要求释放开始时间不早于旧计划（斜率不会变陡）
+    pub fn is_at_least_as_strict_as(&self, current: &LockSchedule) -> bool {
+        let later_start = match (*self, *current) {
+            (
+                LockSchedule::Linear { start_ts, .. },
+                LockSchedule::Linear { start_ts: current_start, .. },
+            ) => start_ts >= current_start,
+            _ => true,
+        };
+
+        later_start
+            && self.first_unlock_ts() >= current.first_unlock_ts()
+            && self.full_unlock_ts() >= current.full_unlock_ts()
+    }
+}
+
+/// 金库代币统计账户
+///
+/// 每个金库、每个 mint 一个，记录该代币的累计存取款数量，
+/// 使 `Vault` 上的 `total_deposits` / `total_withdrawals` 只统计 SOL。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 32 字节  - mint (Pubkey)
+/// 8 字节   - total_deposits (u64)
+/// 8 字节   - total_withdrawals (u64)
+/// 1 字节   - bump (u8)
+/// --------
+/// 89 字节 总计
+/// ```
+#[account]
+#[derive(InitSpace)]
+pub struct TokenTotals {
+    /// 所属金库
+    pub vault: Pubkey,
+
+    /// 代币 mint
+    pub mint: Pubkey,
+
+    /// 累计总存款数量（代币最小单位）
+    pub total_deposits: u64,
+
+    /// 累计总提款数量（代币最小单位）
+    pub total_withdrawals: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// 存款回执账户
+///
+/// 每个金库、每个存款人一个，在首次 `deposit` 时自动创建，
+/// 记录该存款人的累计存款和已退款金额。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 32 字节  - depositor (Pubkey)
+/// 8 字节   - total_deposited (u64)
+/// 8 字节   - total_refunded (u64)
+/// 1 字节   - bump (u8)
+/// --------
+/// 89 字节 总计
+/// ```
+#[account]
+#[derive(InitSpace)]
+pub struct DepositorReceipt {
+    /// 所属金库
+    pub vault: Pubkey,
+
+    /// 存款人
+    pub depositor: Pubkey,
+
+    /// 累计存款金额（lamports）
+    pub total_deposited: u64,
+
+    /// 累计已退款金额（lamports）
+    pub total_refunded: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DepositorReceipt {
+    /// 存款人尚未取回的存款
+    pub fn balance(&self) -> u64 {
+        self.total_deposited.saturating_sub(self.total_refunded)
+    }
+}
+
+/// 基点分母（10000 bps = 100%）
+pub const MAX_BPS: u16 = 10_000;
+
+/// 白名单中接收者的最大数量
+pub const MAX_ALLOWED_RECEIVERS: usize = 16;
+
+/// 接收者白名单账户
+///
+/// 每个金库一个，由 `enable_allowlist` 创建。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 8 字节   - delay_seconds (i64)
+/// 788 字节 - receivers (4 字节长度 + 16 × 49)
+/// 1 字节   - bump (u8)
+/// --------
+/// 837 字节 总计
+/// ```
+#[account]
+#[derive(InitSpace)]
+pub struct ReceiverAllowlist {
+    /// 所属金库
+    pub vault: Pubkey,
+
+    /// 增删接收者的生效延迟（秒）
+    pub delay_seconds: i64,
+
+    /// 接收者列表（包括尚未生效和待移除的）
+    #[max_len(16)]
+    pub receivers: Vec<AllowedReceiver>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// 白名单中的接收者
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct AllowedReceiver {
+    /// 接收者地址
+    pub receiver: Pubkey,
+
+    /// 生效时间（Unix 时间戳，秒）
+    pub active_ts: i64,
+
+    /// 移除时间（Unix 时间戳，秒），None 表示未排队移除
+    pub removal_ts: Option<i64>,
+}
+
+impl AllowedReceiver {
+    /// 在 `now` 时刻是否可以接收提款
+    pub fn is_active(&self, now: i64) -> bool {
+        now >= self.active_ts && !matches!(self.removal_ts, Some(ts) if now >= ts)
+    }
+}
+
+impl ReceiverAllowlist {
+    /// `receiver` 在 `now` 时刻是否可以接收提款
+    pub fn is_allowed(&self, receiver: &Pubkey, now: i64) -> bool {
+        self.receivers.iter().any(|r| r.receiver == *receiver && r.is_active(now))
+    }
+
+    /// 清理已到期移除的接收者
+    pub fn prune(&mut self, now: i64) {
+        self.receivers.retain(|r| !matches!(r.removal_ts, Some(ts) if now >= ts));
+    }
+}
+
+/// 操作历史中保存的最大记录数
+pub const MAX_HISTORY_ENTRIES: usize = 64;
+
+/// 操作历史账户
+///
+/// 每个金库一个，由 `enable_history` 创建。固定大小的环形缓冲区：
+/// 写满后新记录覆盖最旧的记录，`next` 指向下一条要覆盖的位置。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节    - Anchor 账户判别器
+/// 32 字节   - vault (Pubkey)
+/// 3140 字节 - entries (4 字节长度 + 64 × 49)
+/// 1 字节    - next (u8)
+/// 8 字节    - total (u64)
+/// 1 字节    - bump (u8)
+/// --------
+/// 3190 字节 总计
+/// ```
+#[account]
+#[derive(InitSpace)]
+pub struct VaultHistory {
+    /// 所属金库
+    pub vault: Pubkey,
 
-// ============================================================================
-// 数据结构
-// ============================================================================
+    /// 记录（写满前按时间顺序，写满后从 `next` 开始是最旧的记录）
+    #[max_len(64)]
+    pub entries: Vec<HistoryEntry>,
 
-/// 金库账户数据结构
+    /// 下一条记录的写入位置
+    pub next: u8,
+
+    /// 累计写入的记录数（包括已被覆盖的）
+    pub total: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// 操作类型
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum HistoryKind {
+    /// 存款（actor 为存款人）
+    Deposit,
+    /// 提款（actor 为所有者）
+    Withdraw,
+    /// 转移所有权（actor 为新所有者，amount 为 0）
+    AuthorityTransfer,
+}
+
+/// 一条操作记录
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct HistoryEntry {
+    /// 操作类型
+    pub kind: HistoryKind,
+
+    /// 操作者
+    pub actor: Pubkey,
+
+    /// 金额（lamports）
+    ///
+    /// 提款记录的是离开金库的总金额（含手续费，与 `total_withdrawals` 一致）；
+    /// 接收者实际收到的金额和手续费分别见 `Withdrawn` 事件的 `amount` 和 `fee`
+    pub amount: u64,
+
+    /// 时间（Unix 时间戳，秒）
+    pub timestamp: i64,
+}
+
+impl VaultHistory {
+    /// 追加一条记录，写满后覆盖最旧的记录
+    pub fn push(&mut self, entry: HistoryEntry) {
+        let next = self.next as usize;
+        if self.entries.len() < MAX_HISTORY_ENTRIES {
+            self.entries.push(entry);
+        } else {
+            self.entries[next] = entry;
+        }
+        self.next = ((next + 1) % MAX_HISTORY_ENTRIES) as u8;
+        self.total = self.total.saturating_add(1);
+    }
+
+    /// 按时间顺序（从旧到新）返回所有记录
+    pub fn recent(&self) -> Vec<HistoryEntry> {
+        let oldest = if self.entries.len() < MAX_HISTORY_ENTRIES { 0 } else { self.next as usize };
+        self.entries[oldest..]
+            .iter()
+            .chain(&self.entries[..oldest])
+            .copied()
+            .collect()
+    }
+}
+
+/// 每个创建者最多登记的金库数量
+pub const MAX_REGISTRY_VAULTS: usize = 256;
+
+/// 金库登记表账户
 ///
-/// # 字段说明
-/// - `authority`: 当前所有者（可以通过 transfer_authority 改变）
-/// - `original_authority`: 原始创建者（永不改变，用于 PDA 计算）
-/// - `vault_name`: 金库名称（用于标识和 PDA 计算）
-/// - `total_deposits`: 累计存款金额（只增不减）
-/// - `total_withdrawals`: 累计提款金额（只增不减）
-/// - `bump`: PDA bump seed（用于签名）
+/// 每个创建者一个（PDA: `[b"registry", original_authority]`），由创建者的第一个
+/// `initialize` 创建。空间按实际登记数量分配：登记金库时扩容，关闭金库时缩容。
+/// 所有权转移后金库仍留在创建者的登记表中（与金库 PDA 一样按创建者派生）。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节          - Anchor 账户判别器
+/// 32 字节         - owner (Pubkey)
+/// 4 + 32 × n 字节 - vaults (Vec<Pubkey>)
+/// 1 字节          - bump (u8)
+/// --------
+/// 45 + 32 × n 字节 总计
+/// ```
+#[account]
+pub struct VaultRegistry {
+    /// 创建者（登记表 PDA 的 seed）
+    pub owner: Pubkey,
+
+    /// 已登记的金库地址（关闭时移除，顺序不保证）
+    pub vaults: Vec<Pubkey>,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl VaultRegistry {
+    /// 登记 `count` 个金库时的账户大小（包括 8 字节判别器）
+    pub const fn space(count: usize) -> usize {
+        8 + 32 + 4 + 32 * count + 1
+    }
+}
+
+/// 提款手续费上限（基点，1000 bps = 10%）
+pub const MAX_WITHDRAW_FEE_BPS: u16 = 1_000;
+
+/// 全局配置账户
+///
+/// 全局唯一（PDA: `[b"config"]`），由程序的升级权限通过 `init_config` 创建。
+/// 还没有创建时所有提款都不收取手续费。
 ///
 /// # 存储空间
+/// ```text
+/// 8 字节  - Anchor 账户判别器
+/// 32 字节 - admin (Pubkey)
+/// 32 字节 - treasury (Pubkey)
+/// 2 字节  - withdraw_fee_bps (u16)
+/// 1 字节  - bump (u8)
+/// --------
+/// 75 字节 总计
 /// ```
-/// 8 字节   - Anchor 账户判别器（account discriminator）
-/// 32 字节  - authority (Pubkey)
-/// 32 字节  - original_authority (Pubkey)
-/// 36 字节  - vault_name (4 字节长度 + 最多 32 字节 UTF-8)
-/// 8 字节   - total_deposits (u64)
-/// 8 字节   - total_withdrawals (u64)
+#[account]
+#[derive(InitSpace)]
+pub struct VaultConfig {
+    /// 配置管理员（可以修改手续费和协议金库）
+    pub admin: Pubkey,
+
+    /// 协议金库（接收提款手续费）
+    pub treasury: Pubkey,
+
+    /// 提款手续费（基点，最多 MAX_WITHDRAW_FEE_BPS）
+    pub withdraw_fee_bps: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// 委托账户
+///
+/// 每个金库、每个委托人一个，由所有者通过 `grant_delegate` 创建，
+/// 通过 `revoke_delegate` 关闭。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 32 字节  - delegate (Pubkey)
+/// 8 字节   - allowance (u64)
+/// 9 字节   - expires_at (1 字节 Option + 8)
+/// 8 字节   - total_withdrawn (u64)
 /// 1 字节   - bump (u8)
 /// --------
-/// 125 字节 总计
+/// 98 字节 总计
 /// ```
+#[account]
+#[derive(InitSpace)]
+pub struct Delegate {
+    /// 所属金库
+    pub vault: Pubkey,
+
+    /// 委托人
+    pub delegate: Pubkey,
+
+    /// 剩余可提取额度（lamports）
+    pub allowance: u64,
+
+    /// 过期时间（Unix 时间戳，秒），None 表示永不过期
+    pub expires_at: Option<i64>,
+
+    /// 该委托人累计提取金额（lamports）
+    pub total_withdrawn: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// 支付流账户
 ///
-/// # 为什么需要 original_authority？
-/// - PDA 地址由 seeds 决定：[b"vault", authority, vault_name]
-/// - 如果使用 authority 作为 seed，转移所有权后地址会改变
-/// - 使用 original_authority 确保 PDA 地址永不改变
-/// - 这样所有权可以转移，但金库地址保持不变
+/// 由所有者通过 `create_stream` 创建，收款人通过 `claim_stream` 领取，
+/// 所有者通过 `cancel_stream` 结算并关闭。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 32 字节  - recipient (Pubkey)
+/// 8 字节   - id (u64)
+/// 8 字节   - rate_per_second (u64)
+/// 8 字节   - start_ts (i64)
+/// 8 字节   - end_ts (i64)
+/// 8 字节   - claimed (u64)
+/// 1 字节   - bump (u8)
+/// --------
+/// 113 字节 总计
+/// ```
 #[account]
 #[derive(InitSpace)]
-pub struct Vault {
-    /// 当前所有者（可以被转移）
-    /// 拥有提款、转移所有权、关闭金库的权限
-    pub authority: Pubkey,
+pub struct PaymentStream {
+    /// 所属金库
+    pub vault: Pubkey,
 
-    /// 原始创建者（永不改变）
-    /// 用于 PDA seeds 计算，确保金库地址不变
-    pub original_authority: Pubkey,
+    /// 收款人
+    pub recipient: Pubkey,
 
-    /// 金库名称（最多 32 字符）
-    /// 用于标识金库和 PDA seeds 计算
-    /// 同一用户可以创建多个不同名称的金库
-    #[max_len(32)]
+    /// 支付流编号（PDA seeds 的一部分）
+    pub id: u64,
+
+    /// 每秒累积的金额（lamports）
+    pub rate_per_second: u64,
+
+    /// 开始时间（Unix 时间戳，秒）
+    pub start_ts: i64,
+
+    /// 结束时间（Unix 时间戳，秒）
+    pub end_ts: i64,
+
+    /// 已领取的金额（lamports）
+    pub claimed: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PaymentStream {
+    /// 截至 `now` 已累积的总金额，溢出 u64 时返回 None
+    pub fn accrued(&self, now: i64) -> Option<u64> {
+        let elapsed = now.min(self.end_ts).saturating_sub(self.start_ts).max(0) as u128;
+        u64::try_from(elapsed * self.rate_per_second as u128).ok()
+    }
+
+    /// 截至 `now` 可领取的金额
+    pub fn claimable(&self, now: i64) -> Result<u64> {
+        let accrued = self.accrued(now).ok_or(VaultError::Overflow)?;
+        Ok(accrued.saturating_sub(self.claimed))
+    }
+}
+
+/// 多签提案账户
+///
+/// 由多签成员通过 `propose` 创建，批准数达到阈值后由对应的
+/// `execute_*` 指令执行并关闭，租金退还给发起人。
+///
+/// # 存储空间
+/// ```text
+/// 8 字节   - Anchor 账户判别器
+/// 32 字节  - vault (Pubkey)
+/// 32 字节  - proposer (Pubkey)
+/// 8 字节   - id (u64)
+/// 73 字节  - action (1 字节枚举标签 + 最多 72 字节)
+/// 324 字节 - approvals (4 + 10 × 32)
+/// 1 字节   - bump (u8)
+/// 8 字节   - multisig_nonce (u64)
+/// --------
+/// 486 字节 总计
+/// ```
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    /// 所属金库
+    pub vault: Pubkey,
+
+    /// 发起人（执行后接收提案账户租金）
+    pub proposer: Pubkey,
+
+    /// 提案编号（PDA seeds 的一部分）
+    pub id: u64,
+
+    /// 提案要执行的操作
+    pub action: ProposalAction,
+
+    /// 已批准的多签成员
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// 发起时金库的 `multisig_nonce`，与金库当前的不一致时不能批准或执行
+    pub multisig_nonce: u64,
+}
+
+/// 多签提案的操作
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ProposalAction {
+    /// 提取 SOL（对应 execute_withdrawal）
+    Withdraw {
+        /// 接收者
+        receiver: Pubkey,
+        /// 提款金额（lamports）
+        amount: u64,
+    },
+
+    /// 提取 SPL 代币（对应 execute_token_withdrawal）
+    WithdrawToken {
+        /// 代币 mint
+        mint: Pubkey,
+        /// 接收者代币账户
+        receiver_token_account: Pubkey,
+        /// 提款数量（代币最小单位）
+        amount: u64,
+    },
+
+    /// 转移所有权（对应 execute_authority_transfer）
+    TransferAuthority {
+        /// 新的所有者
+        new_authority: Pubkey,
+    },
+
+    /// 关闭金库（对应 execute_close）
+    CloseVault {
+        /// 接收金库剩余余额的地址
+        destination: Pubkey,
+    },
+}
+
+// ============================================================================
+// 事件定义
+// ============================================================================
+//
+// 所有状态变化都发出 Anchor 事件，索引服务可以通过 IDL 解码，
+// 不需要解析 msg! 日志。余额字段均为操作完成后的金库 lamports。
+
+/// 金库已创建
+#[event]
+pub struct VaultInitialized {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 所有者（创建者）
+    pub authority: Pubkey,
+    /// 金库名称
     pub vault_name: String,
+    /// 创建时的 slot
+    pub slot: u64,
+}
 
-    /// 累计总存款金额（lamports）
-    /// 只会增加，记录历史存款总额
+/// SOL 存款
+#[event]
+pub struct Deposited {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 存款人
+    pub depositor: Pubkey,
+    /// 存款金额（lamports）
+    pub amount: u64,
+    /// 存款后的累计存款
     pub total_deposits: u64,
+    /// 存款后的金库余额
+    pub balance: u64,
+    /// 外部参考号（订单号、发票号等，None 表示没有）
+    pub reference: Option<[u8; 32]>,
+    /// 存款时的 slot
+    pub slot: u64,
+}
 
-    /// 累计总提款金额（lamports）
-    /// 只会增加，记录历史提款总额
+/// SOL 提款（包括所有者、委托人、多签提案和存款人退款）
+#[event]
+pub struct Withdrawn {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 发起提款的签名者（所有者、委托人、多签执行者或退款的存款人）
+    pub withdrawer: Pubkey,
+    /// 接收者
+    pub receiver: Pubkey,
+    /// 接收者收到的金额（lamports，不含手续费）
+    pub amount: u64,
+    /// 转给协议金库的手续费（lamports，退款不收取）
+    pub fee: u64,
+    /// 提款后的累计提款（含手续费）
     pub total_withdrawals: u64,
+    /// 提款后的金库余额
+    pub balance: u64,
+    /// 提款时的 slot
+    pub slot: u64,
+}
 
-    /// PDA bump seed
-    /// 用于程序签名时生成有效的 PDA
-    pub bump: u8,
+/// 所有权已转移（一步转移、两步转移或多签提案）
+#[event]
+pub struct AuthorityTransferred {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 原所有者
+    pub old_authority: Pubkey,
+    /// 新所有者
+    pub new_authority: Pubkey,
+    /// 转移时的 slot
+    pub slot: u64,
+}
+
+/// 金库已关闭
+#[event]
+pub struct VaultClosed {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 关闭时的所有者
+    pub authority: Pubkey,
+    /// 接收剩余余额的地址
+    pub destination: Pubkey,
+    /// 转给 destination 的金额（包括租金）
+    pub amount: u64,
+    /// 最终累计存款
+    pub total_deposits: u64,
+    /// 最终累计提款
+    pub total_withdrawals: u64,
+    /// 关闭时的 slot
+    pub slot: u64,
+}
+
+/// 守护者暂停了金库
+#[event]
+pub struct VaultPaused {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 守护者
+    pub guardian: Pubkey,
+    /// 暂停时的 slot
+    pub slot: u64,
+}
+
+/// 所有者和守护者共同解除了暂停
+#[event]
+pub struct VaultUnpaused {
+    /// 金库 PDA
+    pub vault: Pubkey,
+    /// 所有者
+    pub authority: Pubkey,
+    /// 守护者
+    pub guardian: Pubkey,
+    /// 解除时的 slot
+    pub slot: u64,
+}
+
+/// 全局配置已创建或更新
+#[event]
+pub struct VaultConfigUpdated {
+    /// 配置管理员
+    pub admin: Pubkey,
+    /// 协议金库
+    pub treasury: Pubkey,
+    /// 提款手续费（基点）
+    pub withdraw_fee_bps: u16,
+    /// 更新时的 slot
+    pub slot: u64,
 }
 
 // ============================================================================
@@ -496,6 +6802,65 @@ pub struct Vault {
 /// - InvalidAmount: 6001
 /// - InsufficientFunds: 6002
 /// - Overflow: 6003
+/// - StillLocked: 6004
+/// - InvalidLockSchedule: 6005
+/// - LockCannotBeLoosened: 6006
+/// - MultisigRequired: 6007
+/// - MultisigNotEnabled: 6008
+/// - MultisigAlreadyEnabled: 6009
+/// - InvalidMultisigConfig: 6010
+/// - NotMultisigSigner: 6011
+/// - InvalidProposalId: 6012
+/// - AlreadyApproved: 6013
+/// - NotEnoughApprovals: 6014
+/// - ProposalActionMismatch: 6015
+/// - StaleProposal: 6016
+/// - OneStepTransferDisabled: 6017
+/// - NotPendingAuthority: 6018
+/// - RefundsDisabled: 6019
+/// - RefundExceedsDeposits: 6020
+/// - ReceiptRequired: 6021
+/// - RateLimitExceeded: 6022
+/// - InvalidRateLimit: 6023
+/// - DelegateExpired: 6024
+/// - AllowanceExceeded: 6025
+/// - InvalidExpiry: 6026
+/// - ReceiverNotAllowed: 6027
+/// - ReceiverAlreadyListed: 6028
+/// - ReceiverNotListed: 6029
+/// - AllowlistFull: 6030
+/// - InvalidAllowlistDelay: 6031
+/// - VaultPaused: 6032
+/// - VaultNotPaused: 6033
+/// - NotGuardian: 6034
+/// - InvalidStream: 6035
+/// - NothingToClaim: 6036
+/// - MigrationRequired: 6037
+/// - AlreadyMigrated: 6038
+/// - InvalidVaultName: 6039
+/// - FundsStaked: 6040
+/// - StakeAccountInUse: 6041
+/// - NoActiveStake: 6042
+/// - VaultNotEmpty: 6043
+/// - LinkedAccountsOpen: 6044
+/// - TokenAccountNotEmpty: 6045
+/// - InvalidDestination: 6046
+/// - AllowlistAccountRequired: 6047
+/// - NotProposer: 6048
+/// - HistoryAccountRequired: 6049
+/// - InvalidBps: 6050
+/// - InvalidReference: 6051
+/// - ReferenceRequired: 6052
+/// - DepositTooSmall: 6053
+/// - MaxBalanceExceeded: 6054
+/// - NotBeneficiary: 6055
+/// - OwnerStillActive: 6056
+/// - InvalidInactivityPeriod: 6057
+/// - RegistryFull: 6058
+/// - FeeTooHigh: 6059
+/// - TreasuryMismatch: 6060
+/// - NotUpgradeAuthority: 6061
+/// - InvalidTreasury: 6062
 #[error_code]
 pub enum VaultError {
     /// 金库名称超过 32 字符限制
@@ -515,4 +6880,435 @@ pub enum VaultError {
     /// （例如 total_deposits 或 total_withdrawals 超过 u64::MAX）
     #[msg("数值溢出")]
     Overflow,
+
+    /// 时间锁尚未到期，或提款金额超过已解锁的部分
+    #[msg("金库仍处于锁定期")]
+    StillLocked,
+
+    /// 时间锁参数不合法
+    /// （线性释放要求 start_ts <= cliff_ts <= end_ts 且 start_ts < end_ts）
+    #[msg("时间锁参数不合法")]
+    InvalidLockSchedule,
+
+    /// 新的时间锁比当前的更宽松
+    #[msg("时间锁只能收紧，不能放宽")]
+    LockCannotBeLoosened,
+
+    /// 金库已启用多签，单签指令被禁用
+    #[msg("金库已启用多签，必须通过提案执行")]
+    MultisigRequired,
+
+    /// 金库未启用多签，不能使用提案指令
+    #[msg("金库未启用多签")]
+    MultisigNotEnabled,
+
+    /// 金库已经是多签模式
+    #[msg("金库已启用多签")]
+    MultisigAlreadyEnabled,
+
+    /// 多签成员为空、超过上限、有重复，或阈值为 0 / 大于成员数
+    #[msg("多签配置不合法")]
+    InvalidMultisigConfig,
+
+    /// 调用者不是多签成员
+    #[msg("不是多签成员")]
+    NotMultisigSigner,
+
+    /// 提案编号必须等于金库当前的提案计数
+    #[msg("提案编号不正确")]
+    InvalidProposalId,
+
+    /// 该成员已经批准过此提案
+    #[msg("已经批准过该提案")]
+    AlreadyApproved,
+
+    /// 批准数未达到阈值
+    #[msg("批准数不足")]
+    NotEnoughApprovals,
+
+    /// 提案操作与执行指令或传入账户不匹配
+    #[msg("提案操作与执行指令不匹配")]
+    ProposalActionMismatch,
+
+    /// 提案由之前的多签配置发起
+    #[msg("提案已失效（多签配置已变更）")]
+    StaleProposal,
+
+    /// 金库未开启一步转移，应使用 propose_authority / accept_authority
+    #[msg("一步转移已禁用，请使用两步转移")]
+    OneStepTransferDisabled,
+
+    /// 没有待接受的转移，或签名者不是待接受的新所有者
+    #[msg("不是待接受的新所有者")]
+    NotPendingAuthority,
+
+    /// 金库未开启可退款模式
+    #[msg("金库未开启退款")]
+    RefundsDisabled,
+
+    /// 退款金额超过存款人尚未取回的存款
+    #[msg("退款金额超过剩余存款")]
+    RefundExceedsDeposits,
+
+    /// 金库开启了可退款模式，存款必须传入存款回执
+    #[msg("可退款金库的存款必须传入存款回执")]
+    ReceiptRequired,
+
+    /// 当前时间窗口内的累计提款超过限额
+    #[msg("超过提款限额")]
+    RateLimitExceeded,
+
+    /// 提款限额或窗口长度为 0
+    #[msg("提款限额参数不合法")]
+    InvalidRateLimit,
+
+    /// 委托已过期
+    #[msg("委托已过期")]
+    DelegateExpired,
+
+    /// 提款金额超过委托剩余额度
+    #[msg("超过委托额度")]
+    AllowanceExceeded,
+
+    /// 过期时间早于当前时间
+    #[msg("过期时间不合法")]
+    InvalidExpiry,
+
+    /// 接收者不在白名单中（或尚未生效、已被移除）
+    #[msg("接收者不在白名单中")]
+    ReceiverNotAllowed,
+
+    /// 接收者已在白名单中
+    #[msg("接收者已在白名单中")]
+    ReceiverAlreadyListed,
+
+    /// 接收者不在白名单中（或已经在等待移除），无法移除
+    #[msg("白名单中没有该接收者")]
+    ReceiverNotListed,
+
+    /// 白名单已满
+    #[msg("白名单已满")]
+    AllowlistFull,
+
+    /// 白名单延迟必须大于 0
+    #[msg("白名单延迟不合法")]
+    InvalidAllowlistDelay,
+
+    /// 金库已被守护者暂停
+    #[msg("金库已被暂停")]
+    VaultPaused,
+
+    /// 金库未被暂停，无需解除
+    #[msg("金库未被暂停")]
+    VaultNotPaused,
+
+    /// 调用者不是金库的守护者
+    #[msg("不是金库的守护者")]
+    NotGuardian,
+
+    /// 支付流参数不合法（编号不连续、速率为 0、时间区间不合法或总额溢出）
+    #[msg("支付流参数不合法")]
+    InvalidStream,
+
+    /// 支付流没有可领取的金额
+    #[msg("没有可领取的金额")]
+    NothingToClaim,
+
+    /// 旧版金库没有空间保存新字段，需要先调用 migrate_vault
+    #[msg("金库需要先迁移到新版本")]
+    MigrationRequired,
+
+    /// 金库已经是当前版本
+    #[msg("金库已经是当前版本")]
+    AlreadyMigrated,
+
+    /// 金库名称为空或不是规范形式（只允许小写字母、数字、- 和 _）
+    #[msg("金库名称不合法")]
+    InvalidVaultName,
+
+    /// 可用余额不足，部分 SOL 仍在质押中（需要先解除并取回质押）
+    #[msg("资金仍在质押中")]
+    FundsStaked,
+
+    /// 金库已有质押账户（同时只能有一个）
+    #[msg("金库已有质押账户")]
+    StakeAccountInUse,
+
+    /// 金库没有质押
+    #[msg("金库没有质押")]
+    NoActiveStake,
+
+    /// 金库中租金以外的余额超过 require_empty 阈值
+    #[msg("金库余额超过阈值，不能关闭")]
+    VaultNotEmpty,
+
+    /// 还有委托、支付流或代币账户没有关闭
+    #[msg("还有关联账户没有关闭")]
+    LinkedAccountsOpen,
+
+    /// 金库代币账户还有余额
+    #[msg("代币账户还有余额")]
+    TokenAccountNotEmpty,
+
+    /// 接收地址不合法（例如金库本身）
+    #[msg("接收地址不合法")]
+    InvalidDestination,
+
+    /// 开启了接收者白名单，关闭金库时必须传入白名单账户
+    #[msg("需要传入接收者白名单账户")]
+    AllowlistAccountRequired,
+
+    /// 只有发起人可以取消未失效的提案
+    #[msg("只有发起人可以取消提案")]
+    NotProposer,
+
+    /// 金库开启了操作历史，但没有传入该金库的历史账户
+    #[msg("需要传入金库的历史账户")]
+    HistoryAccountRequired,
+
+    /// 比例（基点）不在 1 - 10000 之间
+    #[msg("比例必须在 1 到 10000 bps 之间")]
+    InvalidBps,
+
+    /// 存款参考号全为 0
+    #[msg("存款参考号不能全为 0")]
+    InvalidReference,
+
+    /// 金库要求存款参考号，但没有传入
+    #[msg("该金库的存款必须带参考号")]
+    ReferenceRequired,
+
+    /// 存款金额低于金库的最低存款金额
+    #[msg("存款金额低于最低存款金额")]
+    DepositTooSmall,
+
+    /// 存款后金库余额会超过上限
+    #[msg("存款后余额超过金库上限")]
+    MaxBalanceExceeded,
+
+    /// 签名者不是金库的受益人
+    #[msg("只有受益人可以领取金库")]
+    NotBeneficiary,
+
+    /// 所有者还没有超过不活跃期限
+    #[msg("所有者仍然活跃，还不能领取")]
+    OwnerStillActive,
+
+    /// 不活跃期限必须大于 0
+    #[msg("不活跃期限必须大于 0")]
+    InvalidInactivityPeriod,
+
+    /// 登记表已满（每个创建者最多 MAX_REGISTRY_VAULTS 个金库）
+    #[msg("金库登记表已满")]
+    RegistryFull,
+
+    /// 手续费超过 MAX_WITHDRAW_FEE_BPS
+    #[msg("手续费超过上限")]
+    FeeTooHigh,
+
+    /// 收取手续费时没有传入协议金库，或与配置中的不一致
+    #[msg("协议金库与配置不一致")]
+    TreasuryMismatch,
+
+    /// 只有程序的升级权限可以创建全局配置
+    #[msg("只有程序的升级权限可以执行此操作")]
+    NotUpgradeAuthority,
+
+    /// 协议金库不存在、是可执行账户，或余额低于租金豁免最低余额
+    #[msg("协议金库必须是已达到租金豁免最低余额的账户")]
+    InvalidTreasury,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 所有可变长度字段都取最大长度的金库（序列化后正好填满账户空间）
+    fn full_vault() -> Vault {
+        let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+        Vault {
+            authority: key(1),
+            original_authority: key(2),
+            vault_name: "v".repeat(32),
+            total_deposits: 3,
+            total_withdrawals: 4,
+            bump: 255,
+            lock: Some(LockSchedule::Linear { start_ts: 5, cliff_ts: 6, end_ts: 7 }),
+            multisig: Some(Multisig {
+                signers: (0..MAX_MULTISIG_SIGNERS as u8).map(|i| key(100 + i)).collect(),
+                threshold: 7,
+                proposal_count: 8,
+            }),
+            multisig_nonce: 9,
+            pending_authority: Some(key(10)),
+            one_step_transfer: true,
+            refundable: true,
+            rate_limit: Some(RateLimit {
+                max_per_window: 11,
+                window_seconds: 12,
+                window_start: 13,
+                spent_in_window: 14,
+                pending: Some(RateLimitChange { max_per_window: 15, window_seconds: 16, effective_ts: 17 }),
+            }),
+            receiver_allowlist: true,
+            guardian: Some(key(18)),
+            paused: true,
+            stream_count: 19,
+            version: VAULT_VERSION,
+            staked_lamports: 20,
+            open_delegates: 21,
+            open_streams: 22,
+            open_token_accounts: 23,
+            open_proposals: 24,
+            receipt_balance: 25,
+            history_enabled: true,
+            require_reference: true,
+            min_deposit: 26,
+            max_balance: Some(27),
+            beneficiary: Some(key(28)),
+            inactivity_period: 29,
+            last_heartbeat: 30,
+        }
+    }
+
+    /// 与 Anchor 写回账户相同：写入大小固定的账户数据
+    fn serialize(vault: &Vault, size: usize) -> Result<Vec<u8>> {
+        let mut data = vec![0; size];
+        vault.try_serialize(&mut data.as_mut_slice())?;
+        Ok(data)
+    }
+
+    /// 各版本布局的账户大小（下标为版本号）
+    const LAYOUT_SIZES: [usize; VAULT_VERSION as usize + 1] = [125, 630, 638, 654, 655, 656, 673, 722];
+
+    /// 版本 `version` 的金库：之后版本新增的字段取默认值，
+    /// 各版本最后一个字段的末尾字节不为 0（可以检查序列化后是否正好填满账户空间）
+    fn vault_at(version: u8) -> Vault {
+        let mut vault = full_vault();
+        vault.staked_lamports = u64::MAX;
+        vault.receipt_balance = u64::MAX;
+        vault.max_balance = Some(u64::MAX);
+        vault.last_heartbeat = i64::MAX;
+        vault.clear_fields_after(version);
+        vault.version = version;
+        vault
+    }
+
+    #[test]
+    fn vault_layout_sizes() {
+        assert_eq!(LAYOUT_SIZES[0], LEGACY_VAULT_SIZE);
+        assert_eq!(LAYOUT_SIZES[VAULT_VERSION as usize], 8 + Vault::INIT_SPACE);
+    }
+
+    #[test]
+    fn every_version_round_trips_at_its_size() {
+        for (version, size) in LAYOUT_SIZES.into_iter().enumerate() {
+            let vault = vault_at(version as u8);
+
+            // 可变长度字段取最大长度时正好填满该版本的账户空间
+            let data = serialize(&vault, size).unwrap();
+            assert!(serialize(&vault, size - 1).is_err(), "version {version}");
+            assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), vault, "version {version}");
+
+            // 之后版本新增的字段写不下，必须先迁移
+            if (version as u8) < VAULT_VERSION {
+                let mut changed = vault.clone();
+                changed.last_heartbeat = 1;
+                assert_eq!(
+                    serialize(&changed, size).unwrap_err(),
+                    VaultError::MigrationRequired.into(),
+                    "version {version}"
+                );
+
+                // 迁移后按当前布局写回，原有字段保留
+                let mut migrated = vault.clone();
+                migrated.version = VAULT_VERSION;
+                let data = serialize(&migrated, 8 + Vault::INIT_SPACE).unwrap();
+                assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), migrated);
+            }
+        }
+    }
+
+    #[test]
+    fn old_version_ignores_stale_bytes() {
+        // 版本 2 的金库在之前写回时更长（pending_authority 为 Some），
+        // 布局之后残留的字节不属于该版本，读取时忽略
+        let mut vault = vault_at(VAULT_VERSION_STAKING);
+        let mut data = serialize(&vault, LAYOUT_SIZES[VAULT_VERSION_STAKING as usize]).unwrap();
+        vault.pending_authority = None;
+        vault.try_serialize(&mut data.as_mut_slice()).unwrap();
+        assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), vault);
+    }
+
+    #[test]
+    fn legacy_vault_round_trip() {
+        let legacy = LegacyVault {
+            authority: Pubkey::new_from_array([1; 32]),
+            original_authority: Pubkey::new_from_array([2; 32]),
+            vault_name: "v".repeat(32),
+            total_deposits: 3,
+            total_withdrawals: 4,
+            bump: 255,
+        };
+        let vault = Vault::from(legacy);
+
+        let data = serialize(&vault, LEGACY_VAULT_SIZE).unwrap();
+        assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), vault);
+
+        // 旧版账户放不下新字段
+        let mut changed = vault.clone();
+        changed.guardian = Some(Pubkey::new_unique());
+        assert_eq!(
+            serialize(&changed, LEGACY_VAULT_SIZE).unwrap_err(),
+            VaultError::MigrationRequired.into()
+        );
+
+        // 迁移（扩容并更新版本号）后可以写入新字段
+        changed.version = VAULT_VERSION;
+        let data = serialize(&changed, 8 + Vault::INIT_SPACE).unwrap();
+        assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), changed);
+    }
+
+    #[test]
+    fn versioned_vault_round_trip() {
+        let vault = full_vault();
+
+        // 序列化后正好填满账户空间
+        let data = serialize(&vault, 8 + Vault::INIT_SPACE).unwrap();
+        assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), vault);
+
+        // Option 为 None 时账户末尾是空白字节，同样可以读回
+        let mut sparse = vault.clone();
+        sparse.lock = None;
+        sparse.multisig = None;
+        sparse.rate_limit = None;
+        let data = serialize(&sparse, 8 + Vault::INIT_SPACE).unwrap();
+        assert_eq!(Vault::try_deserialize(&mut data.as_slice()).unwrap(), sparse);
+    }
+
+    #[test]
+    fn lock_rejects_extreme_timestamps() {
+        let extreme = LockSchedule::Linear { start_ts: i64::MIN, cliff_ts: i64::MIN, end_ts: i64::MAX };
+        assert!(!extreme.is_valid());
+        assert!(!LockSchedule::Cliff { unlock_ts: i64::MAX }.is_valid());
+        assert!(!LockSchedule::Cliff { unlock_ts: -1 }.is_valid());
+
+        let bounded = LockSchedule::Linear { start_ts: 0, cliff_ts: 0, end_ts: MAX_LOCK_TIMESTAMP };
+        assert!(bounded.is_valid());
+        assert!(LockSchedule::Cliff { unlock_ts: MAX_LOCK_TIMESTAMP }.is_valid());
+    }
+
+    #[test]
+    fn lock_unlocked_amount_does_not_overflow() {
+        // 已经写入链上的极端计划仍然可以计算，不会 panic
+        let extreme = LockSchedule::Linear { start_ts: i64::MIN, cliff_ts: i64::MIN, end_ts: i64::MAX };
+        assert_eq!(extreme.unlocked_amount(u64::MAX, 0, 0), 1 << 63);
+        assert_eq!(extreme.unlocked_amount(u64::MAX, 0, i64::MIN), 0);
+        assert_eq!(extreme.unlocked_amount(u64::MAX, 0, i64::MAX), u64::MAX);
+
+        let linear = LockSchedule::Linear { start_ts: 100, cliff_ts: 150, end_ts: 200 };
+        assert_eq!(linear.unlocked_amount(1_000, 0, 149), 0);
+        assert_eq!(linear.unlocked_amount(1_000, 100, 150), 400);
+        assert_eq!(linear.unlocked_amount(1_000, 0, 200), u64::MAX);
+    }
 }
@@ -0,0 +1,1205 @@
+// ============================================================================
+// Token Vault 集成测试（进程内验证器）
+// ============================================================================
+//
+// 使用 LiteSVM 在进程内加载编译好的 token_vault.so，不需要 solana-test-validator，可以离线运行：
+//
+//     anchor build                 # 或 cargo build-sbf，输出到 target/deploy
+//     cargo test -p token-vault
+//
+// 测试不会自己编译程序：设置了 SBF_OUT_DIR 时从该目录加载，否则从 target/deploy 加载。
+// 两者都没有 .so 时每个测试打印提示后跳过；设置了 SBF_OUT_DIR 却找不到 .so 时直接失败（CI 用这种方式）。
+// Anchor 0.32 的 CPI 只能在 SBF 中执行，无法用原生编译的 processor 代替。
+//
+// 覆盖：
+// - initialize：字段初始化、重复创建失败
+// - deposit：余额、累计存款、存款回执
+// - withdraw：租金豁免最低余额的边界
+// - withdraw：按全局配置收取提款手续费
+// - withdraw_all / withdraw_bps：返回值是扣除手续费后的到账金额
+// - remove_receiver：已经排队移除的接收者不能再次移除
+// - claim_stream：与提款一样收取手续费
+// - cancel_stream：余额不足时只结算可用余额，支付流照样关闭
+// - stake_idle：与提款一样受时间锁限制
+// - 转移所有权后金库 PDA 不变（由 original_authority 派生）
+// - close_vault：余额退还、require_empty 阈值
+// - 金库登记表：同一所有者创建多个金库，创建时登记并扩容，关闭时移除并缩容
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use litesvm::LiteSVM;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_stake_interface::{program as stake_program, stake_history};
+use token_vault::{
+    find_config_address, find_registry_address, find_vault_address, DepositorReceipt, LockSchedule,
+    ReceiverAllowlist, Vault, VaultConfig, VaultError, VaultRegistry, STAKE_CONFIG_ID, VAULT_VERSION,
+};
+
+const VAULT_NAME: &str = "test-vault";
+
+// ============================================================================
+// 辅助函数
+// ============================================================================
+
+/// 编译好的程序路径
+///
+/// 设置了 SBF_OUT_DIR 时使用其中的 .so，否则使用工作区的 target/deploy
+fn program_path() -> PathBuf {
+    match std::env::var_os("SBF_OUT_DIR") {
+        Some(dir) => PathBuf::from(dir).join("token_vault.so"),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/token_vault.so"),
+    }
+}
+
+/// 创建加载了 token-vault 的 LiteSVM
+///
+/// 没有编译好的程序时返回 None，调用方跳过测试；显式设置了 SBF_OUT_DIR 时缺少 .so 视为错误
+fn start() -> Option<LiteSVM> {
+    let path = program_path();
+    if !path.exists() {
+        assert!(std::env::var_os("SBF_OUT_DIR").is_none(), "SBF_OUT_DIR 中找不到 {}", path.display());
+        eprintln!(
+            "跳过：找不到 {}，请先运行 `anchor build`（或 `cargo build-sbf`）编译 token-vault",
+            path.display()
+        );
+        return None;
+    }
+    let mut svm = LiteSVM::new();
+    svm.add_program_from_file(token_vault::ID, &path).unwrap();
+    Some(svm)
+}
+
+/// 用 `signers` 签名并发送一组指令（第一个签名者支付手续费）
+fn send(
+    svm: &mut LiteSVM,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), TransactionError> {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    // 避免连续发送相同的交易被当作重复交易
+    svm.expire_blockhash();
+    result.map(|_| ()).map_err(|failed| failed.err)
+}
+
+/// 发送一组指令，读取最后一条指令的 u64 返回值（`set_return_data`）
+fn send_for_u64(svm: &mut LiteSVM, instructions: &[Instruction], signers: &[&Keypair]) -> u64 {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        svm.latest_blockhash(),
+    );
+    let meta = svm.send_transaction(tx).expect("交易应该成功");
+    svm.expire_blockhash();
+    assert_eq!(meta.return_data.program_id, token_vault::ID);
+    u64::from_le_bytes(meta.return_data.data.as_slice().try_into().expect("返回值应该是 u64"))
+}
+
+/// 断言交易因指定的 VaultError 失败
+fn assert_vault_error(result: std::result::Result<(), TransactionError>, expected: VaultError) {
+    let err = result.expect_err("交易应该失败");
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into())),
+    );
+}
+
+/// 创建一个有 `lamports` 余额的新钱包
+fn funded_keypair(svm: &mut LiteSVM, lamports: u64) -> Keypair {
+    let keypair = Keypair::new();
+    svm.airdrop(&keypair.pubkey(), lamports).unwrap();
+    keypair
+}
+
+fn fetch_vault(svm: &LiteSVM, vault: Pubkey) -> Vault {
+    let account = svm.get_account(&vault).expect("金库不存在");
+    Vault::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn lamports(svm: &LiteSVM, address: Pubkey) -> u64 {
+    svm.get_balance(&address).unwrap_or(0)
+}
+
+/// 金库的可用余额（总余额 − 租金豁免最低余额）
+fn available(svm: &LiteSVM, vault: Pubkey) -> u64 {
+    let account = svm.get_account(&vault).unwrap();
+    account.lamports - svm.minimum_balance_for_rent_exemption(account.data.len())
+}
+
+fn fetch_registry(svm: &LiteSVM, owner: &Pubkey) -> VaultRegistry {
+    let account = svm.get_account(&find_registry_address(owner).0).unwrap();
+    VaultRegistry::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn receipt_address(vault: &Pubkey, depositor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"receipt", vault.as_ref(), depositor.as_ref()],
+        &token_vault::ID,
+    )
+    .0
+}
+
+fn allowlist_address(vault: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allowlist", vault.as_ref()], &token_vault::ID).0
+}
+
+/// 直接写入全局配置（init_config 要求调用者是程序升级权限，LiteSVM 加载的程序没有 ProgramData）
+fn set_config(svm: &mut LiteSVM, treasury: &Pubkey, withdraw_fee_bps: u16) {
+    let (address, bump) = find_config_address();
+    let config = VaultConfig {
+        admin: Pubkey::new_unique(),
+        treasury: *treasury,
+        withdraw_fee_bps,
+        bump,
+    };
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    svm.set_account(
+        address,
+        SolanaAccount {
+            lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: token_vault::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+// ============================================================================
+// 指令构造
+// ============================================================================
+
+fn initialize_ix(authority: &Pubkey, vault_name: &str) -> Instruction {
+    let (vault, _) = find_vault_address(authority, vault_name);
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::Initialize {
+            vault,
+            registry: find_registry_address(authority).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::Initialize {
+            vault_name: vault_name.to_string(),
+        }
+        .data(),
+    }
+}
+
+fn deposit_ix(vault: &Pubkey, depositor: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::Deposit {
+            vault: *vault,
+            receipt: Some(receipt_address(vault, depositor)),
+            depositor: *depositor,
+            system_program: system_program::ID,
+            history: None,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::Deposit {
+            amount,
+            reference: None,
+        }
+        .data(),
+    }
+}
+
+fn withdraw_ix(vault: &Pubkey, authority: &Pubkey, receiver: &Pubkey, amount: u64) -> Instruction {
+    // 测试中默认不创建全局配置，提款不收取手续费
+    withdraw_with_treasury_ix(vault, authority, receiver, None, amount)
+}
+
+fn withdraw_with_treasury_ix(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    receiver: &Pubkey,
+    treasury: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::Withdraw {
+            vault: *vault,
+            authority: *authority,
+            receiver: *receiver,
+            allowlist: None,
+            history: None,
+            system_program: system_program::ID,
+            config: find_config_address().0,
+            treasury,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::Withdraw { amount }.data(),
+    }
+}
+
+/// `withdraw_all` / `withdraw_bps` 与 `withdraw` 共用账户，`bps` 为 None 时提取全部
+fn withdraw_share_ix(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    receiver: &Pubkey,
+    treasury: Option<Pubkey>,
+    bps: Option<u16>,
+) -> Instruction {
+    let mut ix = withdraw_with_treasury_ix(vault, authority, receiver, treasury, 0);
+    ix.data = match bps {
+        Some(bps) => token_vault::instruction::WithdrawBps { bps }.data(),
+        None => token_vault::instruction::WithdrawAll {}.data(),
+    };
+    ix
+}
+
+fn propose_authority_ix(vault: &Pubkey, authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::TransferAuthority {
+            vault: *vault,
+            authority: *authority,
+            history: None,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::ProposeAuthority {
+            new_authority: *new_authority,
+        }
+        .data(),
+    }
+}
+
+fn accept_authority_ix(vault: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::AcceptAuthority {
+            vault: *vault,
+            new_authority: *new_authority,
+            history: None,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::AcceptAuthority {}.data(),
+    }
+}
+
+fn close_vault_ix(vault: &Pubkey, authority: &Pubkey, require_empty: Option<u64>) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::CloseVault {
+            vault: *vault,
+            authority: *authority,
+            destination: None,
+            history: None,
+            allowlist: None,
+            registry: find_registry_address(authority).0,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::CloseVault { require_empty }.data(),
+    }
+}
+
+fn enable_allowlist_ix(vault: &Pubkey, authority: &Pubkey, delay_seconds: i64) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::EnableAllowlist {
+            vault: *vault,
+            allowlist: allowlist_address(vault),
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::EnableAllowlist { delay_seconds }.data(),
+    }
+}
+
+fn manage_allowlist_accounts(vault: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    token_vault::accounts::ManageAllowlist {
+        vault: *vault,
+        allowlist: allowlist_address(vault),
+        authority: *authority,
+    }
+    .to_account_metas(None)
+}
+
+fn add_receiver_ix(vault: &Pubkey, authority: &Pubkey, receiver: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: manage_allowlist_accounts(vault, authority),
+        data: token_vault::instruction::AddReceiver { receiver: *receiver }.data(),
+    }
+}
+
+fn remove_receiver_ix(vault: &Pubkey, authority: &Pubkey, receiver: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: manage_allowlist_accounts(vault, authority),
+        data: token_vault::instruction::RemoveReceiver { receiver: *receiver }.data(),
+    }
+}
+
+fn stream_address(vault: &Pubkey, stream_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"stream", vault.as_ref(), &stream_id.to_le_bytes()],
+        &token_vault::ID,
+    )
+    .0
+}
+
+fn create_stream_ix(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    rate_per_second: u64,
+    start_ts: i64,
+    end_ts: i64,
+) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::CreateStream {
+            vault: *vault,
+            stream: stream_address(vault, 0),
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::CreateStream {
+            stream_id: 0,
+            recipient: *recipient,
+            rate_per_second,
+            start_ts,
+            end_ts,
+        }
+        .data(),
+    }
+}
+
+fn claim_stream_ix(vault: &Pubkey, recipient: &Pubkey, treasury: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::ClaimStream {
+            vault: *vault,
+            stream: stream_address(vault, 0),
+            recipient: *recipient,
+            allowlist: None,
+            config: find_config_address().0,
+            treasury,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::ClaimStream {}.data(),
+    }
+}
+
+fn cancel_stream_ix(vault: &Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::CancelStream {
+            vault: *vault,
+            stream: stream_address(vault, 0),
+            recipient: *recipient,
+            authority: *authority,
+            allowlist: None,
+            config: find_config_address().0,
+            treasury: None,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::CancelStream {}.data(),
+    }
+}
+
+fn set_lock_ix(vault: &Pubkey, authority: &Pubkey, lock: LockSchedule) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::SetLock {
+            vault: *vault,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::SetLock { lock }.data(),
+    }
+}
+
+fn stake_idle_ix(vault: &Pubkey, authority: &Pubkey, vote_account: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::StakeIdle {
+            vault: *vault,
+            stake_account: Pubkey::find_program_address(&[b"stake", vault.as_ref()], &token_vault::ID).0,
+            vote_account: *vote_account,
+            authority: *authority,
+            stake_program: stake_program::ID,
+            system_program: system_program::ID,
+            rent: anchor_lang::solana_program::sysvar::rent::ID,
+            clock: anchor_lang::solana_program::sysvar::clock::ID,
+            stake_history: stake_history::ID,
+            stake_config: STAKE_CONFIG_ID,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::StakeIdle { amount }.data(),
+    }
+}
+
+/// 创建一个有 10 SOL 的所有者和它的金库
+fn setup_vault(svm: &mut LiteSVM) -> (Keypair, Pubkey) {
+    let authority = funded_keypair(svm, 10 * LAMPORTS_PER_SOL);
+    send(
+        svm,
+        &[initialize_ix(&authority.pubkey(), VAULT_NAME)],
+        &[&authority],
+    )
+    .unwrap();
+    let (vault, _) = find_vault_address(&authority.pubkey(), VAULT_NAME);
+    (authority, vault)
+}
+
+// ============================================================================
+// initialize
+// ============================================================================
+
+#[test]
+fn initialize_sets_up_vault() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault_address) = setup_vault(&mut svm);
+
+    let (_, bump) = find_vault_address(&authority.pubkey(), VAULT_NAME);
+    let vault = fetch_vault(&svm, vault_address);
+    assert_eq!(vault.authority, authority.pubkey());
+    assert_eq!(vault.original_authority, authority.pubkey());
+    assert_eq!(vault.vault_name, VAULT_NAME);
+    assert_eq!(vault.total_deposits, 0);
+    assert_eq!(vault.total_withdrawals, 0);
+    assert_eq!(vault.bump, bump);
+    assert_eq!(vault.version, VAULT_VERSION);
+
+    // 新金库只有租金，没有可用余额
+    assert_eq!(available(&svm, vault_address), 0);
+}
+
+#[test]
+fn initialize_twice_fails() {
+    let Some(mut svm) = start() else { return };
+    let (authority, _) = setup_vault(&mut svm);
+
+    // 同一个所有者和名称只能创建一次
+    let result = send(
+        &mut svm,
+        &[initialize_ix(&authority.pubkey(), VAULT_NAME)],
+        &[&authority],
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn initialize_rejects_non_canonical_name() {
+    let Some(mut svm) = start() else { return };
+    let authority = funded_keypair(&mut svm, LAMPORTS_PER_SOL);
+
+    // 指令构造时不做校验，直接用原始名称派生地址
+    let result = send(
+        &mut svm,
+        &[initialize_ix(&authority.pubkey(), "My-Vault")],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::InvalidVaultName);
+}
+
+// ============================================================================
+// deposit
+// ============================================================================
+
+#[test]
+fn deposit_updates_balance_and_receipt() {
+    let Some(mut svm) = start() else { return };
+    let (_, vault) = setup_vault(&mut svm);
+    let depositor = funded_keypair(&mut svm, 5 * LAMPORTS_PER_SOL);
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &depositor.pubkey(), LAMPORTS_PER_SOL)],
+        &[&depositor],
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        &[deposit_ix(
+            &vault,
+            &depositor.pubkey(),
+            LAMPORTS_PER_SOL / 2,
+        )],
+        &[&depositor],
+    )
+    .unwrap();
+
+    let expected = LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2;
+    assert_eq!(available(&svm, vault), expected);
+    assert_eq!(fetch_vault(&svm, vault).total_deposits, expected);
+
+    // 回执记录存款人的累计存款
+    let receipt = svm
+        .get_account(&receipt_address(&vault, &depositor.pubkey()))
+        .unwrap();
+    let receipt = DepositorReceipt::try_deserialize(&mut receipt.data.as_slice()).unwrap();
+    assert_eq!(receipt.depositor, depositor.pubkey());
+    assert_eq!(receipt.total_deposited, expected);
+}
+
+#[test]
+fn deposit_without_receipt_skips_receipt() {
+    let Some(mut svm) = start() else { return };
+    let (_, vault) = setup_vault(&mut svm);
+    let depositor = funded_keypair(&mut svm, 5 * LAMPORTS_PER_SOL);
+
+    // 未开启可退款模式时回执是可选的，不传入就不会创建
+    let ix = Instruction {
+        program_id: token_vault::ID,
+        accounts: token_vault::accounts::Deposit {
+            vault,
+            receipt: None,
+            depositor: depositor.pubkey(),
+            system_program: system_program::ID,
+            history: None,
+        }
+        .to_account_metas(None),
+        data: token_vault::instruction::Deposit {
+            amount: LAMPORTS_PER_SOL,
+            reference: None,
+        }
+        .data(),
+    };
+    send(&mut svm, &[ix], &[&depositor]).unwrap();
+
+    assert_eq!(available(&svm, vault), LAMPORTS_PER_SOL);
+    assert!(svm
+        .get_account(&receipt_address(&vault, &depositor.pubkey()))
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn deposit_zero_fails() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+
+    let result = send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), 0)],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::InvalidAmount);
+}
+
+// ============================================================================
+// withdraw：租金豁免最低余额
+// ============================================================================
+
+#[test]
+fn withdraw_cannot_touch_rent_reserve() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let receiver = Pubkey::new_unique();
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    )
+    .unwrap();
+    let vault_lamports = lamports(&svm, vault);
+    let available_before = available(&svm, vault);
+    assert_eq!(available_before, LAMPORTS_PER_SOL);
+
+    // 多提 1 lamport 就会动用租金
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(
+            &vault,
+            &authority.pubkey(),
+            &receiver,
+            available_before + 1,
+        )],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::InsufficientFunds);
+
+    // 正好提取全部可用余额，金库只剩租金
+    send(
+        &mut svm,
+        &[withdraw_ix(
+            &vault,
+            &authority.pubkey(),
+            &receiver,
+            available_before,
+        )],
+        &[&authority],
+    )
+    .unwrap();
+    assert_eq!(available(&svm, vault), 0);
+    assert_eq!(lamports(&svm, vault), vault_lamports - available_before);
+    assert_eq!(lamports(&svm, receiver), available_before);
+
+    // 之后连 1 lamport 也不能再提
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(&vault, &authority.pubkey(), &receiver, 1)],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::InsufficientFunds);
+    assert_eq!(fetch_vault(&svm, vault).total_withdrawals, available_before);
+}
+
+#[test]
+fn withdraw_zero_fails() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(
+            &vault,
+            &authority.pubkey(),
+            &authority.pubkey(),
+            0,
+        )],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::InvalidAmount);
+}
+
+#[test]
+fn withdraw_requires_authority() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let attacker = funded_keypair(&mut svm, LAMPORTS_PER_SOL);
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    )
+    .unwrap();
+
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(
+            &vault,
+            &attacker.pubkey(),
+            &attacker.pubkey(),
+            1,
+        )],
+        &[&attacker],
+    );
+    let err = result.expect_err("交易应该失败");
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(anchor_lang::error::ErrorCode::ConstraintHasOne.into()),
+        ),
+    );
+}
+
+// ============================================================================
+// withdraw：提款手续费
+// ============================================================================
+
+#[test]
+fn withdraw_pays_fee() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let treasury = funded_keypair(&mut svm, LAMPORTS_PER_SOL).pubkey();
+    let receiver = Pubkey::new_unique();
+    set_config(&mut svm, &treasury, 100);
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    )
+    .unwrap();
+
+    // 收费时必须传入协议金库
+    let amount = LAMPORTS_PER_SOL / 2;
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(&vault, &authority.pubkey(), &receiver, amount)],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::TreasuryMismatch);
+
+    let treasury_before = lamports(&svm, treasury);
+    send(
+        &mut svm,
+        &[withdraw_with_treasury_ix(
+            &vault,
+            &authority.pubkey(),
+            &receiver,
+            Some(treasury),
+            amount,
+        )],
+        &[&authority],
+    )
+    .unwrap();
+
+    // 100 bps = 1%，从接收者到账金额中扣除；累计提款按含手续费的金额计算
+    let fee = amount / 100;
+    assert_eq!(lamports(&svm, receiver), amount - fee);
+    assert_eq!(lamports(&svm, treasury), treasury_before + fee);
+    assert_eq!(available(&svm, vault), LAMPORTS_PER_SOL - amount);
+    assert_eq!(fetch_vault(&svm, vault).total_withdrawals, amount);
+}
+
+#[test]
+fn withdraw_all_and_bps_return_net_amount() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let treasury = funded_keypair(&mut svm, LAMPORTS_PER_SOL).pubkey();
+    let receiver = Pubkey::new_unique();
+    set_config(&mut svm, &treasury, 100);
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    )
+    .unwrap();
+
+    // 提取一半：返回值是接收者到账金额（扣除 1% 手续费）
+    let half = LAMPORTS_PER_SOL / 2;
+    let returned = send_for_u64(
+        &mut svm,
+        &[withdraw_share_ix(&vault, &authority.pubkey(), &receiver, Some(treasury), Some(5_000))],
+        &[&authority],
+    );
+    assert_eq!(returned, half - half / 100);
+    assert_eq!(lamports(&svm, receiver), returned);
+
+    // 提取剩余全部
+    let rest = available(&svm, vault);
+    let returned = send_for_u64(
+        &mut svm,
+        &[withdraw_share_ix(&vault, &authority.pubkey(), &receiver, Some(treasury), None)],
+        &[&authority],
+    );
+    assert_eq!(returned, rest - rest / 100);
+    assert_eq!(lamports(&svm, receiver), half - half / 100 + returned);
+    assert_eq!(available(&svm, vault), 0);
+}
+
+// ============================================================================
+// remove_receiver：排队中的移除
+// ============================================================================
+
+#[test]
+fn remove_receiver_rejects_queued_removal() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+    let receiver = Pubkey::new_unique();
+    let delay = 60;
+
+    send(
+        &mut svm,
+        &[
+            enable_allowlist_ix(&vault, &owner, delay),
+            add_receiver_ix(&vault, &owner, &receiver),
+        ],
+        &[&authority],
+    )
+    .unwrap();
+
+    // 等待接收者生效后移除：进入延迟移除队列
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += delay;
+    svm.set_sysvar(&clock);
+    send(
+        &mut svm,
+        &[remove_receiver_ix(&vault, &owner, &receiver)],
+        &[&authority],
+    )
+    .unwrap();
+
+    let account = svm.get_account(&allowlist_address(&vault)).unwrap();
+    let allowlist = ReceiverAllowlist::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(allowlist.receivers.len(), 1);
+    assert_eq!(allowlist.receivers[0].removal_ts, Some(clock.unix_timestamp + delay));
+
+    // 再次移除不会被当作新的移除
+    let result = send(
+        &mut svm,
+        &[remove_receiver_ix(&vault, &owner, &receiver)],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::ReceiverNotListed);
+}
+
+// ============================================================================
+// claim_stream：手续费
+// ============================================================================
+
+#[test]
+fn claim_stream_pays_fee() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+    let recipient = funded_keypair(&mut svm, LAMPORTS_PER_SOL);
+    let treasury = funded_keypair(&mut svm, LAMPORTS_PER_SOL).pubkey();
+    set_config(&mut svm, &treasury, 100);
+
+    send(&mut svm, &[deposit_ix(&vault, &owner, LAMPORTS_PER_SOL)], &[&authority]).unwrap();
+
+    // 每秒 0.001 SOL，100 秒后累积 0.1 SOL
+    let rate = LAMPORTS_PER_SOL / 1_000;
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    send(
+        &mut svm,
+        &[create_stream_ix(&vault, &owner, &recipient.pubkey(), rate, now, now + 1_000)],
+        &[&authority],
+    )
+    .unwrap();
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 100;
+    svm.set_sysvar(&clock);
+
+    // 收款人也不能绕过手续费：收费时必须传入协议金库
+    let result = send(
+        &mut svm,
+        &[claim_stream_ix(&vault, &recipient.pubkey(), None)],
+        &[&authority, &recipient],
+    );
+    assert_vault_error(result, VaultError::TreasuryMismatch);
+
+    let recipient_before = lamports(&svm, recipient.pubkey());
+    let treasury_before = lamports(&svm, treasury);
+    send(
+        &mut svm,
+        &[claim_stream_ix(&vault, &recipient.pubkey(), Some(treasury))],
+        &[&authority, &recipient],
+    )
+    .unwrap();
+
+    // 100 bps = 1%，从领取金额中扣除
+    let claimed = 100 * rate;
+    let fee = claimed / 100;
+    assert_eq!(lamports(&svm, recipient.pubkey()), recipient_before + claimed - fee);
+    assert_eq!(lamports(&svm, treasury), treasury_before + fee);
+    assert_eq!(fetch_vault(&svm, vault).total_withdrawals, claimed);
+}
+
+// ============================================================================
+// cancel_stream：余额不足
+// ============================================================================
+
+#[test]
+fn cancel_underfunded_stream_settles_available_balance() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+    let recipient = Pubkey::new_unique();
+
+    send(&mut svm, &[deposit_ix(&vault, &owner, LAMPORTS_PER_SOL)], &[&authority]).unwrap();
+
+    // 每秒 1 SOL，10 秒后累积 10 SOL，金库只有 1 SOL
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    send(
+        &mut svm,
+        &[create_stream_ix(&vault, &owner, &recipient, LAMPORTS_PER_SOL, now, now + 100)],
+        &[&authority],
+    )
+    .unwrap();
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 10;
+    svm.set_sysvar(&clock);
+
+    // 取消不会因为余额不足失败：结算可用余额，未结算的部分作废
+    send(&mut svm, &[cancel_stream_ix(&vault, &owner, &recipient)], &[&authority]).unwrap();
+    assert_eq!(lamports(&svm, recipient), LAMPORTS_PER_SOL);
+    assert_eq!(available(&svm, vault), 0);
+    assert!(svm.get_account(&stream_address(&vault, 0)).is_none());
+    assert_eq!(fetch_vault(&svm, vault).open_streams, 0);
+
+    // 支付流已关闭，金库可以关闭
+    send(&mut svm, &[close_vault_ix(&vault, &owner, None)], &[&authority]).unwrap();
+}
+
+// ============================================================================
+// stake_idle：时间锁
+// ============================================================================
+
+#[test]
+fn stake_idle_respects_lock() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+
+    send(&mut svm, &[deposit_ix(&vault, &owner, 2 * LAMPORTS_PER_SOL)], &[&authority]).unwrap();
+
+    // 锁定到一小时后：质押和提款一样被拒绝
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    send(
+        &mut svm,
+        &[set_lock_ix(&vault, &owner, LockSchedule::Cliff { unlock_ts: now + 3_600 })],
+        &[&authority],
+    )
+    .unwrap();
+
+    let result = send(
+        &mut svm,
+        &[stake_idle_ix(&vault, &owner, &Pubkey::new_unique(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::StillLocked);
+    assert_eq!(available(&svm, vault), 2 * LAMPORTS_PER_SOL);
+    assert_eq!(fetch_vault(&svm, vault).staked_lamports, 0);
+}
+
+// ============================================================================
+// 转移所有权：PDA 由 original_authority 派生，地址不变
+// ============================================================================
+
+#[test]
+fn authority_transfer_keeps_vault_address() {
+    let Some(mut svm) = start() else { return };
+    let (old_authority, vault) = setup_vault(&mut svm);
+    let new_authority = funded_keypair(&mut svm, LAMPORTS_PER_SOL);
+    let receiver = Pubkey::new_unique();
+
+    send(
+        &mut svm,
+        &[deposit_ix(
+            &vault,
+            &old_authority.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        &[&old_authority],
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        &[propose_authority_ix(
+            &vault,
+            &old_authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        &[&old_authority],
+    )
+    .unwrap();
+    send(
+        &mut svm,
+        &[accept_authority_ix(&vault, &new_authority.pubkey())],
+        &[&new_authority],
+    )
+    .unwrap();
+
+    // 所有者变了，但 original_authority 和 PDA 地址不变
+    let state = fetch_vault(&svm, vault);
+    assert_eq!(state.authority, new_authority.pubkey());
+    assert_eq!(state.original_authority, old_authority.pubkey());
+    assert_eq!(
+        find_vault_address(&state.original_authority, VAULT_NAME).0,
+        vault
+    );
+    assert_ne!(
+        find_vault_address(&new_authority.pubkey(), VAULT_NAME).0,
+        vault
+    );
+
+    // 新所有者可以在原地址提款，旧所有者不能
+    send(
+        &mut svm,
+        &[withdraw_ix(
+            &vault,
+            &new_authority.pubkey(),
+            &receiver,
+            LAMPORTS_PER_SOL / 2,
+        )],
+        &[&new_authority],
+    )
+    .unwrap();
+    assert_eq!(lamports(&svm, receiver), LAMPORTS_PER_SOL / 2);
+
+    let result = send(
+        &mut svm,
+        &[withdraw_ix(&vault, &old_authority.pubkey(), &receiver, 1)],
+        &[&old_authority],
+    );
+    assert!(result.is_err());
+
+    // 存款仍然使用同一个地址
+    send(
+        &mut svm,
+        &[deposit_ix(
+            &vault,
+            &old_authority.pubkey(),
+            LAMPORTS_PER_SOL,
+        )],
+        &[&old_authority],
+    )
+    .unwrap();
+    assert_eq!(
+        fetch_vault(&svm, vault).total_deposits,
+        2 * LAMPORTS_PER_SOL
+    );
+}
+
+// ============================================================================
+// close_vault
+// ============================================================================
+
+#[test]
+fn close_vault_returns_all_lamports() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+
+    send(
+        &mut svm,
+        &[deposit_ix(&vault, &authority.pubkey(), LAMPORTS_PER_SOL)],
+        &[&authority],
+    )
+    .unwrap();
+
+    // 还有余额时 require_empty = 0 拒绝关闭
+    let result = send(
+        &mut svm,
+        &[close_vault_ix(&vault, &authority.pubkey(), Some(0))],
+        &[&authority],
+    );
+    assert_vault_error(result, VaultError::VaultNotEmpty);
+
+    // 阈值以内可以关闭，全部 lamports（包括租金）和登记表缩容退还的租金都给所有者
+    let vault_lamports = lamports(&svm, vault);
+    let registry = find_registry_address(&authority.pubkey()).0;
+    let registry_lamports = lamports(&svm, registry);
+    let authority_before = lamports(&svm, authority.pubkey());
+    send(
+        &mut svm,
+        &[close_vault_ix(
+            &vault,
+            &authority.pubkey(),
+            Some(LAMPORTS_PER_SOL),
+        )],
+        &[&authority],
+    )
+    .unwrap();
+
+    assert!(svm.get_account(&vault).is_none());
+    let registry_refund = registry_lamports - lamports(&svm, registry);
+    let fee = 5_000;
+    assert_eq!(
+        lamports(&svm, authority.pubkey()),
+        authority_before + vault_lamports + registry_refund - fee,
+    );
+}
+
+#[test]
+fn close_vault_requires_authority() {
+    let Some(mut svm) = start() else { return };
+    let (_, vault) = setup_vault(&mut svm);
+    let attacker = funded_keypair(&mut svm, LAMPORTS_PER_SOL);
+
+    let result = send(
+        &mut svm,
+        &[close_vault_ix(&vault, &attacker.pubkey(), None)],
+        &[&attacker],
+    );
+    assert!(result.is_err());
+    assert!(svm.get_account(&vault).is_some());
+}
+
+// ============================================================================
+// 金库登记表
+// ============================================================================
+
+#[test]
+fn initialize_several_vaults_for_one_owner() {
+    let Some(mut svm) = start() else { return };
+    let (authority, first) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+
+    // 登记表扩容后，同一个所有者仍然可以继续创建金库
+    let names = ["second", "third"];
+    for name in names {
+        send(&mut svm, &[initialize_ix(&owner, name)], &[&authority]).unwrap();
+    }
+
+    let mut expected = vec![first];
+    expected.extend(names.iter().map(|name| find_vault_address(&owner, name).0));
+    for vault in &expected {
+        assert_eq!(fetch_vault(&svm, *vault).authority, owner);
+    }
+    assert_eq!(fetch_registry(&svm, &owner).vaults, expected);
+}
+
+#[test]
+fn registry_tracks_created_and_closed_vaults() {
+    let Some(mut svm) = start() else { return };
+    let (authority, first) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+    let registry = find_registry_address(&owner).0;
+
+    let state = fetch_registry(&svm, &owner);
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.vaults, vec![first]);
+    assert_eq!(
+        svm.get_account(&registry).unwrap().data.len(),
+        VaultRegistry::space(1)
+    );
+
+    // 第二个金库：登记表扩容 32 字节
+    send(&mut svm, &[initialize_ix(&owner, "second")], &[&authority]).unwrap();
+    let second = find_vault_address(&owner, "second").0;
+    assert_eq!(fetch_registry(&svm, &owner).vaults, vec![first, second]);
+    let account = svm.get_account(&registry).unwrap();
+    assert_eq!(account.data.len(), VaultRegistry::space(2));
+    assert_eq!(
+        account.lamports,
+        svm.minimum_balance_for_rent_exemption(VaultRegistry::space(2))
+    );
+
+    // 关闭第一个金库：从登记表移除并缩容，只保留租金豁免最低余额
+    send(
+        &mut svm,
+        &[close_vault_ix(&first, &owner, None)],
+        &[&authority],
+    )
+    .unwrap();
+    assert_eq!(fetch_registry(&svm, &owner).vaults, vec![second]);
+    let account = svm.get_account(&registry).unwrap();
+    assert_eq!(account.data.len(), VaultRegistry::space(1));
+    assert_eq!(
+        account.lamports,
+        svm.minimum_balance_for_rent_exemption(VaultRegistry::space(1))
+    );
+}
+
+#[test]
+fn close_vault_requires_registry() {
+    let Some(mut svm) = start() else { return };
+    let (authority, vault) = setup_vault(&mut svm);
+    let owner = authority.pubkey();
+
+    // 登记表不能省略或替换成其他账户，否则关闭后金库会留在登记表中
+    let mut ix = close_vault_ix(&vault, &owner, None);
+    let registry = find_registry_address(&owner).0;
+    let meta = ix
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == registry)
+        .unwrap();
+    meta.pubkey = Pubkey::new_unique();
+
+    let result = send(&mut svm, &[ix], &[&authority]);
+    assert!(result.is_err());
+    assert!(svm.get_account(&vault).is_some());
+    assert_eq!(fetch_registry(&svm, &owner).vaults, vec![vault]);
+}
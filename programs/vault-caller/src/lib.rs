@@ -0,0 +1,164 @@
+// ============================================================================
+// Vault Caller - 通过 CPI 使用 token-vault 的示例程序
+// ============================================================================
+//
+// 本程序演示如何把 token-vault 金库的所有者设为另一个程序的 PDA：
+// - 每个用户对应一个 treasury PDA（`[b"treasury", owner]`），作为金库的 authority
+// - 所有需要所有者签名的 token-vault 指令都由本程序通过 `invoke_signed` 代签
+// - 依赖 token-vault 的 `cpi` 特性，使用生成的 `token_vault::cpi` 模块
+//
+// 也作为 token-vault CPI 接口的集成测试（见 tests/vault-caller.ts）
+
+use anchor_lang::prelude::*;
+use token_vault::program::TokenVault;
+use token_vault::{ReceiverAllowlist, Vault, VaultHistory};
+
+declare_id!("2BszcWkbf4ni5uh8zEcFFtH9WGh3hehaw6pXsYvqmwBw");
+
+#[program]
+pub mod vault_caller {
+    use super::*;
+
+    /// 创建由 treasury PDA 所有的金库
+    ///
+    /// # 功能
+    /// - CPI 调用 `token_vault::initialize_with_payer`
+    /// - treasury PDA 作为金库所有者（由本程序签名），`owner` 支付租金
+    ///
+    /// # 参数
+    /// - `vault_name`: 金库名称（规范形式）
+    pub fn create_vault(ctx: Context<CreateVault>, vault_name: String) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let seeds: &[&[u8]] = &[b"treasury", owner.as_ref(), &[ctx.bumps.treasury]];
+        let signer_seeds = &[seeds];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_vault_program.to_account_info(),
+            token_vault::cpi::accounts::InitializeWithPayer {
+                vault: ctx.accounts.vault.to_account_info(),
+                registry: ctx.accounts.registry.to_account_info(),
+                authority: ctx.accounts.treasury.to_account_info(),
+                payer: ctx.accounts.owner.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_vault::cpi::initialize_with_payer(cpi_ctx, vault_name)?;
+
+        msg!("Vault created for treasury: {}", ctx.accounts.treasury.key());
+        Ok(())
+    }
+
+    /// 从 treasury 的金库提取 SOL 到 owner
+    ///
+    /// # 参数
+    /// - `amount`: 提取金额（lamports）
+    pub fn withdraw(ctx: Context<TreasuryWithdraw>, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let seeds: &[&[u8]] = &[b"treasury", owner.as_ref(), &[ctx.bumps.treasury]];
+        let signer_seeds = &[seeds];
+
+        token_vault::cpi::withdraw(ctx.accounts.withdraw_ctx(signer_seeds), amount)?;
+
+        msg!("Withdrew {} lamports via CPI", amount);
+        Ok(())
+    }
+
+    /// 提取金库全部可用余额到 owner
+    ///
+    /// # 返回
+    /// - 实际提取的金额（owner 收到的、扣除手续费后的金额，从 token-vault 的返回值读取，再作为本指令的返回值）
+    pub fn withdraw_all(ctx: Context<TreasuryWithdraw>) -> Result<u64> {
+        let owner = ctx.accounts.owner.key();
+        let seeds: &[&[u8]] = &[b"treasury", owner.as_ref(), &[ctx.bumps.treasury]];
+        let signer_seeds = &[seeds];
+
+        let withdrawn = token_vault::cpi::withdraw_all(ctx.accounts.withdraw_ctx(signer_seeds))?.get();
+
+        msg!("Withdrew all {} lamports via CPI", withdrawn);
+        Ok(withdrawn)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    /// 金库账户（由 token-vault 创建和验证）
+    /// CHECK: 在 token-vault 中按 `[b"vault", treasury, vault_name]` 验证
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// treasury 的金库登记表（由 token-vault 创建和扩容）
+    /// CHECK: 在 token-vault 中按 `[b"registry", treasury]` 验证
+    #[account(mut)]
+    pub registry: UncheckedAccount<'info>,
+
+    /// 金库所有者 PDA（不存储数据，只用于签名）
+    /// CHECK: 只用作签名者，地址由 seeds 验证
+    #[account(seeds = [b"treasury", owner.key().as_ref()], bump)]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_vault_program: Program<'info, TokenVault>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TreasuryWithdraw<'info> {
+    /// 金库账户（所有权和 PDA 由 token-vault 验证）
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+
+    /// 金库所有者 PDA
+    /// CHECK: 只用作签名者，地址由 seeds 验证
+    #[account(seeds = [b"treasury", owner.key().as_ref()], bump)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// 提款接收者，同时决定使用哪个 treasury
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// 接收者白名单（金库开启白名单时需要）
+    pub allowlist: Option<Account<'info, ReceiverAllowlist>>,
+
+    /// 操作历史（金库开启历史记录时需要）
+    #[account(mut)]
+    pub history: Option<Account<'info, VaultHistory>>,
+
+    /// token-vault 的全局配置（决定提款手续费）
+    /// CHECK: 地址由 seeds 验证，内容由 token-vault 读取
+    #[account(seeds = [b"config"], bump, seeds::program = token_vault_program.key())]
+    pub config: UncheckedAccount<'info>,
+
+    /// 协议金库（token-vault 收取手续费时需要）
+    /// CHECK: 在 token-vault 中验证必须等于配置中的 treasury
+    #[account(mut)]
+    pub protocol_treasury: Option<UncheckedAccount<'info>>,
+
+    pub token_vault_program: Program<'info, TokenVault>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TreasuryWithdraw<'info> {
+    /// 构造由 treasury PDA 签名、提款到 owner 的 CPI 上下文
+    fn withdraw_ctx<'a>(
+        &self,
+        signer_seeds: &'a [&'a [&'a [u8]]],
+    ) -> CpiContext<'a, 'a, 'a, 'info, token_vault::cpi::accounts::Withdraw<'info>> {
+        CpiContext::new_with_signer(
+            self.token_vault_program.to_account_info(),
+            token_vault::cpi::accounts::Withdraw {
+                vault: self.vault.to_account_info(),
+                authority: self.treasury.to_account_info(),
+                receiver: self.owner.to_account_info(),
+                allowlist: self.allowlist.as_ref().map(|a| a.to_account_info()),
+                history: self.history.as_ref().map(|h| h.to_account_info()),
+                system_program: self.system_program.to_account_info(),
+                config: self.config.to_account_info(),
+                treasury: self.protocol_treasury.as_ref().map(|t| t.to_account_info()),
+            },
+            signer_seeds,
+        )
+    }
+}
@@ -214,13 +214,18 @@ router.post("/vault/withdraw", async (req, res) => {
 // 关闭金库
 router.post("/vault/close", async (req, res) => {
   try {
-    const { authorityPublicKey, vaultName } = req.body;
+    const { authorityPublicKey, vaultName, destination, requireEmpty } = req.body;
     if (!authorityPublicKey || !vaultName) {
       return res.status(400).json({
         error: "authorityPublicKey and vaultName are required",
       });
     }
-    const result = await vaultService.close(authorityPublicKey, vaultName);
+    const result = await vaultService.close(
+      authorityPublicKey,
+      vaultName,
+      destination,
+      requireEmpty
+    );
     res.json(result);
   } catch (error: any) {
     res.status(500).json({ error: error.message });
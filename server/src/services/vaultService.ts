@@ -2,6 +2,19 @@ import { PublicKey } from "@solana/web3.js";
 import * as anchor from "@coral-xyz/anchor";
 import { solanaConfig } from "../config/solana";
 
+/**
+ * 将用户输入转换为规范形式的金库名称
+ * 规则与程序中的 normalize_vault_name 一致：去掉首尾空白、转为小写，
+ * 结果必须是 1-32 个小写字母、数字、- 或 _
+ */
+export function normalizeVaultName(input: string): string {
+  const name = input.trim().toLowerCase();
+  if (!/^[a-z0-9_-]{1,32}$/.test(name)) {
+    throw new Error("金库名称只能包含 1-32 个小写字母、数字、- 或 _");
+  }
+  return name;
+}
+
 /**
  * Vault 服务
  * 参考 client-ts/token-vault/index.ts
@@ -29,10 +42,12 @@ export class VaultService {
   /**
    * 初始化金库
    */
-  async initialize(authorityPublicKey: string, vaultName: string) {
+  async initialize(authorityPublicKey: string, inputName: string) {
     const program = solanaConfig.vaultProgram;
     if (!program) throw new Error("Vault 程序未加载");
 
+    // 只创建规范名称的金库，避免 "Vault" 和 "vault" 这类看起来相同的金库
+    const vaultName = normalizeVaultName(inputName);
     const authority = new PublicKey(authorityPublicKey);
     const { vaultPda } = this.deriveVaultPda(authority, vaultName);
 
@@ -71,7 +86,7 @@ export class VaultService {
       const amountLamports = new anchor.BN(amount * anchor.web3.LAMPORTS_PER_SOL);
 
       const tx = await (program.methods as any)
-        .deposit(amountLamports)
+        .deposit(amountLamports, null)
         .accounts({
           vault: vaultPda,
           authority: authority,
@@ -102,11 +117,19 @@ export class VaultService {
     try {
       const amountLamports = new anchor.BN(amount * anchor.web3.LAMPORTS_PER_SOL);
 
+      // 全局配置设置了提款手续费时，需要传入配置中的协议金库
+      const [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+      );
+      const config = await (program.account as any).vaultConfig.fetchNullable(configPda);
+
       const tx = await (program.methods as any)
         .withdraw(amountLamports)
         .accounts({
           vault: vaultPda,
           authority: authority,
+          treasury: config ? config.treasury : null,
         })
         .rpc();
 
@@ -158,20 +181,35 @@ export class VaultService {
 
   /**
    * 关闭金库
+   *
+   * @param destination 接收剩余余额的地址（不传时转给所有者）
+   * @param requireEmpty 租金以外的余额超过该值（lamports）时拒绝关闭
    */
-  async close(authorityPublicKey: string, vaultName: string) {
+  async close(
+    authorityPublicKey: string,
+    vaultName: string,
+    destination?: string,
+    requireEmpty?: number
+  ) {
     const program = solanaConfig.vaultProgram;
     if (!program) throw new Error("Vault 程序未加载");
 
     const authority = new PublicKey(authorityPublicKey);
     const { vaultPda } = this.deriveVaultPda(authority, vaultName);
+    // 创建者的金库登记表，关闭时把金库从中移除
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("registry"), authority.toBuffer()],
+      program.programId
+    );
 
     try {
       const tx = await (program.methods as any)
-        .closeVault()
+        .closeVault(requireEmpty !== undefined ? new anchor.BN(requireEmpty) : null)
         .accounts({
           vault: vaultPda,
           authority: authority,
+          destination: destination ? new PublicKey(destination) : null,
+          registry: registryPda,
         })
         .rpc();
 
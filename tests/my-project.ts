@@ -2,6 +2,8 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { MyProject } from "../target/types/my_project";
 import { assert } from "chai";
+import * as fs from "fs";
+import * as path from "path";
 
 describe("my-project", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -77,4 +79,43 @@ describe("my-project", () => {
     assert.ok(counterAccount.count.toNumber() === 1);
     console.log("Counter after decrement:", counterAccount.count.toNumber());
   });
+
+  it("Fails with Underflow when decrementing at zero", async () => {
+    await program.methods
+      .decrement()
+      .accounts({
+        counter: counter.publicKey,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .decrement()
+        .accounts({
+          counter: counter.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail("decrement at zero should fail");
+    } catch (error: any) {
+      assert.instanceOf(error, anchor.AnchorError);
+      assert.equal(error.error.errorCode.code, "Underflow");
+      assert.equal(error.error.errorCode.number, 6001);
+    }
+
+    const counterAccount = await program.account.counter.fetch(counter.publicKey);
+    assert.ok(counterAccount.count.toNumber() === 0);
+  });
+
+  it("Exposes stable CounterError codes in the IDL", async () => {
+    // u64::MAX is unreachable through increment, so Overflow is checked via the IDL.
+    // Read the generated JSON IDL: program.idl is camelCased by Anchor, the JSON keeps Rust casing.
+    const idl = JSON.parse(
+      fs.readFileSync(path.join(__dirname, "../target/idl/my_project.json"), "utf8")
+    );
+    const codes = Object.fromEntries(idl.errors.map((e) => [e.name, e.code]));
+    assert.equal(codes["Overflow"], 6000);
+    assert.equal(codes["Underflow"], 6001);
+  });
 });
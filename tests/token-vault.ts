@@ -6,6 +6,12 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { TokenVault } from "../target/types/token_vault";
 import { PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  createMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
 import { assert } from "chai";
 
 describe("token-vault", () => {
@@ -69,6 +75,24 @@ describe("token-vault", () => {
     console.log(`  ✓ 金库名称: ${vaultAccount.vaultName}`);
   });
 
+  it("❌ 非规范的金库名称无法创建", async () => {
+    for (const badName of ["", "My-Vault", "my vault", "金库"]) {
+      const [badPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), authority.toBuffer(), Buffer.from(badName)],
+        program.programId
+      );
+      try {
+        await program.methods
+          .initialize(badName)
+          .accounts({ vault: badPda, authority })
+          .rpc();
+        assert.fail(`名称 "${badName}" 应该被拒绝`);
+      } catch (error: any) {
+        assert.include(error.message, "InvalidVaultName");
+      }
+    }
+  });
+
   it("💰 存入 SOL", async () => {
     const depositAmount = 0.5 * LAMPORTS_PER_SOL; // 0.5 SOL
 
@@ -76,7 +100,7 @@ describe("token-vault", () => {
     const balanceBefore = await provider.connection.getBalance(vaultPda);
 
     const tx = await program.methods
-      .deposit(new anchor.BN(depositAmount))
+      .deposit(new anchor.BN(depositAmount), null)
       .accounts({
         vault: vaultPda,
         depositor: authority,
@@ -114,7 +138,7 @@ describe("token-vault", () => {
     const previousTotal = vaultBefore.totalDeposits.toNumber();
 
     const tx = await program.methods
-      .deposit(new anchor.BN(depositAmount))
+      .deposit(new anchor.BN(depositAmount), null)
       .accounts({
         vault: vaultPda,
         depositor: authority,
@@ -185,12 +209,30 @@ describe("token-vault", () => {
     console.log(`  ✓ 接收者: ${receiver.toBase58()}`);
   });
 
-  it("🔑 转移所有权", async () => {
+  it("❌ 默认禁用一步转移", async () => {
+    try {
+      await program.methods
+        .transferAuthority(Keypair.generate().publicKey)
+        .accounts({
+          vault: vaultPda,
+          authority: authority,
+        })
+        .rpc();
+
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "OneStepTransferDisabled");
+      console.log(`  ✓ 正确拒绝了一步转移`);
+    }
+  });
+
+  it("🔑 两步转移所有权", async () => {
     const newAuthorityKeypair = Keypair.generate();
     const newAuthority = newAuthorityKeypair.publicKey;
 
+    // 第一步：当前所有者提议
     const tx = await program.methods
-      .transferAuthority(newAuthority)
+      .proposeAuthority(newAuthority)
       .accounts({
         vault: vaultPda,
         authority: authority,
@@ -199,12 +241,35 @@ describe("token-vault", () => {
 
     console.log(`  交易签名: ${tx}`);
 
-    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(
+      vaultAccount.authority.toBase58(),
+      authority.toBase58(),
+      "接受前所有权不应改变"
+    );
+    assert.equal(
+      vaultAccount.pendingAuthority.toBase58(),
+      newAuthority.toBase58(),
+      "待接受的新所有者不正确"
+    );
+
+    // 第二步：新所有者签名接受
+    await program.methods
+      .acceptAuthority()
+      .accounts({
+        vault: vaultPda,
+        newAuthority: newAuthority,
+      })
+      .signers([newAuthorityKeypair])
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vaultPda);
     assert.equal(
       vaultAccount.authority.toBase58(),
       newAuthority.toBase58(),
       "所有权转移不正确"
     );
+    assert.isNull(vaultAccount.pendingAuthority);
 
     console.log(`  ✓ 所有权已转移`);
     console.log(`  ✓ 旧所有者: ${authority.toBase58()}`);
@@ -212,17 +277,49 @@ describe("token-vault", () => {
 
     // 转移回原所有者（为后续测试准备）
     await program.methods
-      .transferAuthority(authority)
+      .proposeAuthority(authority)
       .accounts({
         vault: vaultPda,
         authority: newAuthority,
       })
       .signers([newAuthorityKeypair])
       .rpc();
+    await program.methods
+      .acceptAuthority()
+      .accounts({
+        vault: vaultPda,
+        newAuthority: authority,
+      })
+      .rpc();
 
     console.log(`  ✓ 已转回原所有者`);
   });
 
+  it("❎ 取消所有权转移", async () => {
+    const stranger = Keypair.generate();
+
+    await program.methods
+      .proposeAuthority(stranger.publicKey)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .cancelAuthorityTransfer()
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .acceptAuthority()
+        .accounts({ vault: vaultPda, newAuthority: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NotPendingAuthority");
+      console.log(`  ✓ 取消后无法再接受`);
+    }
+  });
+
   it("❌ 非所有者无法提款", async () => {
     const unauthorizedUser = Keypair.generate();
     const withdrawAmount = 0.1 * LAMPORTS_PER_SOL;
@@ -292,10 +389,11 @@ describe("token-vault", () => {
     const vaultBalanceBefore = await provider.connection.getBalance(vaultPda);
 
     const tx = await program.methods
-      .closeVault()
+      .closeVault(null)
       .accounts({
         vault: vaultPda,
         authority: authority,
+        destination: null,   // 不指定时余额转给所有者
       })
       .rpc();
 
@@ -323,6 +421,1918 @@ describe("token-vault", () => {
   });
 });
 
+describe("token-vault: SPL 代币托管", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const payer = provider.wallet as anchor.Wallet;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "token-vault";
+
+  let vaultPda: PublicKey;
+  let mint: PublicKey;
+  let vaultTokenPda: PublicKey;
+  let tokenTotalsPda: PublicKey;
+  let userToken: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    mint = await createMint(provider.connection, payer.payer, authority, null, 6);
+    userToken = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer.payer,
+        mint,
+        authority
+      )
+    ).address;
+    await mintTo(provider.connection, payer.payer, mint, userToken, authority, 1_000_000);
+
+    [vaultTokenPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_token"), vaultPda.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+    [tokenTotalsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("token_totals"), vaultPda.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("🪙 开通代币托管", async () => {
+    await program.methods
+      .initTokenAccount()
+      .accounts({
+        vault: vaultPda,
+        mint,
+        vaultTokenAccount: vaultTokenPda,
+        tokenTotals: tokenTotalsPda,
+        authority,
+      })
+      .rpc();
+
+    const vaultToken = await getAccount(provider.connection, vaultTokenPda);
+    assert.equal(vaultToken.owner.toBase58(), vaultPda.toBase58(), "代币账户权限应为金库 PDA");
+    assert.equal(Number(vaultToken.amount), 0);
+  });
+
+  it("💰 存入代币", async () => {
+    await program.methods
+      .depositToken(new anchor.BN(400_000))
+      .accounts({
+        vault: vaultPda,
+        vaultTokenAccount: vaultTokenPda,
+        tokenTotals: tokenTotalsPda,
+        depositorTokenAccount: userToken,
+        depositor: authority,
+      })
+      .rpc();
+
+    const vaultToken = await getAccount(provider.connection, vaultTokenPda);
+    const totals = await program.account.tokenTotals.fetch(tokenTotalsPda);
+    assert.equal(Number(vaultToken.amount), 400_000);
+    assert.equal(totals.totalDeposits.toNumber(), 400_000);
+
+    // SOL 统计不受代币存款影响
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.totalDeposits.toNumber(), 0);
+  });
+
+  it("💸 提取代币", async () => {
+    const receiver = Keypair.generate().publicKey;
+    const receiverToken = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer.payer,
+        mint,
+        receiver
+      )
+    ).address;
+
+    await program.methods
+      .withdrawToken(new anchor.BN(150_000))
+      .accounts({
+        vault: vaultPda,
+        vaultTokenAccount: vaultTokenPda,
+        tokenTotals: tokenTotalsPda,
+        receiverTokenAccount: receiverToken,
+        authority,
+      })
+      .rpc();
+
+    const receiverAccount = await getAccount(provider.connection, receiverToken);
+    const totals = await program.account.tokenTotals.fetch(tokenTotalsPda);
+    assert.equal(Number(receiverAccount.amount), 150_000);
+    assert.equal(totals.totalWithdrawals.toNumber(), 150_000);
+  });
+
+  it("❌ 代币余额不足时无法提款", async () => {
+    try {
+      await program.methods
+        .withdrawToken(new anchor.BN(10_000_000))
+        .accounts({
+          vault: vaultPda,
+          vaultTokenAccount: vaultTokenPda,
+          tokenTotals: tokenTotalsPda,
+          receiverTokenAccount: userToken,
+          authority,
+        })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InsufficientFunds");
+    }
+  });
+});
+
+describe("token-vault: 时间锁", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "locked-vault";
+
+  let vaultPda: PublicKey;
+  const now = Math.floor(Date.now() / 1000);
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+
+  it("⏳ 设置到期解锁", async () => {
+    await program.methods
+      .setLock({ cliff: { unlockTs: new anchor.BN(now + 3600) } })
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.isNotNull(vaultAccount.lock);
+  });
+
+  it("❌ 锁定期内无法提款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(1000))
+        .accounts({ vault: vaultPda, authority, receiver: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "StillLocked");
+    }
+  });
+
+  it("❌ 时间锁不能放宽", async () => {
+    try {
+      await program.methods
+        .setLock({ cliff: { unlockTs: new anchor.BN(now + 60) } })
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "LockCannotBeLoosened");
+    }
+  });
+
+  it("⏳ 可以收紧为线性释放", async () => {
+    await program.methods
+      .setLock({
+        linear: {
+          startTs: new anchor.BN(now),
+          cliffTs: new anchor.BN(now + 3600),
+          endTs: new anchor.BN(now + 7200),
+        },
+      })
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.isDefined(vaultAccount.lock.linear);
+  });
+});
+
+describe("token-vault: 多签", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const cosigner = Keypair.generate();
+  const receiver = Keypair.generate().publicKey;
+  const vaultName = "multisig-vault";
+  const withdrawAmount = 0.05 * LAMPORTS_PER_SOL;
+
+  let vaultPda: PublicKey;
+  let proposalPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [proposalPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("proposal"),
+        vaultPda.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+
+  it("🔐 启用 2-of-2 多签", async () => {
+    await program.methods
+      .enableMultisig([authority, cosigner.publicKey], 2)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.multisig.threshold, 2);
+  });
+
+  it("❌ 多签模式下无法直接提款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(1000))
+        .accounts({ vault: vaultPda, authority, receiver })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "MultisigRequired");
+    }
+  });
+
+  it("📝 发起提款提案", async () => {
+    await program.methods
+      .propose(new anchor.BN(0), {
+        withdraw: { receiver, amount: new anchor.BN(withdrawAmount) },
+      })
+      .accounts({ vault: vaultPda, proposal: proposalPda, proposer: authority })
+      .rpc();
+
+    const proposal = await program.account.proposal.fetch(proposalPda);
+    assert.equal(proposal.approvals.length, 1);
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.openProposals, 1);
+  });
+
+  it("❌ 批准数不足时无法执行", async () => {
+    try {
+      await program.methods
+        .executeWithdrawal()
+        .accounts({
+          vault: vaultPda,
+          proposal: proposalPda,
+          proposer: authority,
+          receiver,
+          executor: authority,
+        })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NotEnoughApprovals");
+    }
+  });
+
+  it("✅ 批准后执行提款", async () => {
+    await program.methods
+      .approve()
+      .accounts({ vault: vaultPda, proposal: proposalPda, approver: cosigner.publicKey })
+      .signers([cosigner])
+      .rpc();
+
+    await program.methods
+      .executeWithdrawal()
+      .accounts({
+        vault: vaultPda,
+        proposal: proposalPda,
+        proposer: authority,
+        receiver,
+        executor: authority,
+      })
+      .rpc();
+
+    const receiverBalance = await provider.connection.getBalance(receiver);
+    assert.equal(receiverBalance, withdrawAmount);
+
+    const proposalInfo = await provider.connection.getAccountInfo(proposalPda);
+    assert.isNull(proposalInfo, "提案账户应该已关闭");
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.openProposals, 0);
+  });
+
+  it("🗑️ 只有发起人可以取消提案", async () => {
+    const [closeProposalPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("proposal"),
+        vaultPda.toBuffer(),
+        new anchor.BN(1).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .propose(new anchor.BN(1), { closeVault: { destination: authority } })
+      .accounts({ vault: vaultPda, proposal: closeProposalPda, proposer: authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .cancelProposal()
+        .accounts({
+          vault: vaultPda,
+          proposal: closeProposalPda,
+          proposer: authority,
+          closer: cosigner.publicKey,
+        })
+        .signers([cosigner])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NotProposer");
+    }
+
+    await program.methods
+      .cancelProposal()
+      .accounts({
+        vault: vaultPda,
+        proposal: closeProposalPda,
+        proposer: authority,
+        closer: authority,
+      })
+      .rpc();
+
+    const proposalInfo = await provider.connection.getAccountInfo(closeProposalPda);
+    assert.isNull(proposalInfo, "提案账户应该已关闭");
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.openProposals, 0);
+  });
+});
+
+describe("token-vault: 存款回执与退款", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const depositor = Keypair.generate();
+  const vaultName = "refund-vault";
+  const depositAmount = 0.2 * LAMPORTS_PER_SOL;
+
+  let vaultPda: PublicKey;
+  let receiptPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [receiptPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt"), vaultPda.toBuffer(), depositor.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const sig = await provider.connection.requestAirdrop(
+      depositor.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(sig);
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("🧾 存款时传入回执自动创建", async () => {
+    await program.methods
+      .deposit(new anchor.BN(depositAmount), null)
+      .accounts({ vault: vaultPda, receipt: receiptPda, depositor: depositor.publicKey })
+      .signers([depositor])
+      .rpc();
+
+    const receipt = await program.account.depositorReceipt.fetch(receiptPda);
+    assert.equal(receipt.depositor.toBase58(), depositor.publicKey.toBase58());
+    assert.equal(receipt.totalDeposited.toNumber(), depositAmount);
+  });
+
+  it("❌ 未开启退款时无法退款", async () => {
+    try {
+      await program.methods
+        .refund(new anchor.BN(1000))
+        .accounts({ vault: vaultPda, receipt: receiptPda, depositor: depositor.publicKey })
+        .signers([depositor])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "RefundsDisabled");
+    }
+  });
+
+  it("↩️ 开启退款后存款人取回存款", async () => {
+    await program.methods
+      .enableRefunds()
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const refundAmount = depositAmount / 2;
+    await program.methods
+      .refund(new anchor.BN(refundAmount))
+      .accounts({ vault: vaultPda, receipt: receiptPda, depositor: depositor.publicKey })
+      .signers([depositor])
+      .rpc();
+
+    const receipt = await program.account.depositorReceipt.fetch(receiptPda);
+    assert.equal(receipt.totalRefunded.toNumber(), refundAmount);
+  });
+
+  it("❌ 开启退款后存款必须传入回执", async () => {
+    try {
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({ vault: vaultPda, receipt: null, depositor: depositor.publicKey })
+        .signers([depositor])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "ReceiptRequired");
+    }
+  });
+
+  it("❌ 退款不能超过剩余存款", async () => {
+    try {
+      await program.methods
+        .refund(new anchor.BN(depositAmount))
+        .accounts({ vault: vaultPda, receipt: receiptPda, depositor: depositor.publicKey })
+        .signers([depositor])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "RefundExceedsDeposits");
+    }
+  });
+
+  it("❌ 还有未退款的存款时无法关闭", async () => {
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.receiptBalance.toNumber(), depositAmount / 2);
+
+    try {
+      await program.methods
+        .closeVault(null)
+        .accounts({ vault: vaultPda, authority, destination: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "LinkedAccountsOpen");
+    }
+  });
+});
+
+describe("token-vault: 提款限额", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const receiver = Keypair.generate().publicKey;
+  const vaultName = "limited-vault";
+  const limit = 0.05 * LAMPORTS_PER_SOL;
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.2 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+    await program.methods
+      .setRateLimit(new anchor.BN(limit), new anchor.BN(86400))
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("🚦 限额内可以提款", async () => {
+    await program.methods
+      .withdraw(new anchor.BN(limit))
+      .accounts({ vault: vaultPda, authority, receiver })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.rateLimit.spentInWindow.toNumber(), limit);
+  });
+
+  it("❌ 超过限额时无法提款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(1))
+        .accounts({ vault: vaultPda, authority, receiver })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "RateLimitExceeded");
+    }
+  });
+
+  it("⏳ 放宽限额需要等待一个窗口", async () => {
+    await program.methods
+      .setRateLimit(new anchor.BN(limit * 10), new anchor.BN(86400))
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    assert.equal(vaultAccount.rateLimit.maxPerWindow.toNumber(), limit);
+    assert.isNotNull(vaultAccount.rateLimit.pending);
+  });
+});
+
+describe("token-vault: 委托提款", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const bot = Keypair.generate();
+  const receiver = Keypair.generate().publicKey;
+  const vaultName = "delegated-vault";
+  const allowance = 0.05 * LAMPORTS_PER_SOL;
+
+  let vaultPda: PublicKey;
+  let delegatePda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [delegatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("delegate"), vaultPda.toBuffer(), bot.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.2 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+
+  it("🤝 授权委托人", async () => {
+    await program.methods
+      .grantDelegate(new anchor.BN(allowance), null)
+      .accounts({
+        vault: vaultPda,
+        delegateAccount: delegatePda,
+        delegate: bot.publicKey,
+        authority,
+      })
+      .rpc();
+
+    const delegate = await program.account.delegate.fetch(delegatePda);
+    assert.equal(delegate.allowance.toNumber(), allowance);
+  });
+
+  it("🤖 委托人在额度内提款", async () => {
+    await program.methods
+      .delegateWithdraw(new anchor.BN(allowance / 2))
+      .accounts({
+        vault: vaultPda,
+        delegateAccount: delegatePda,
+        delegate: bot.publicKey,
+        receiver,
+      })
+      .signers([bot])
+      .rpc();
+
+    const delegate = await program.account.delegate.fetch(delegatePda);
+    assert.equal(delegate.allowance.toNumber(), allowance / 2);
+    assert.equal(delegate.totalWithdrawn.toNumber(), allowance / 2);
+  });
+
+  it("❌ 超过额度时无法提款", async () => {
+    try {
+      await program.methods
+        .delegateWithdraw(new anchor.BN(allowance))
+        .accounts({
+          vault: vaultPda,
+          delegateAccount: delegatePda,
+          delegate: bot.publicKey,
+          receiver,
+        })
+        .signers([bot])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "AllowanceExceeded");
+    }
+  });
+
+  it("🚫 撤销委托", async () => {
+    await program.methods
+      .revokeDelegate()
+      .accounts({ vault: vaultPda, delegateAccount: delegatePda, authority })
+      .rpc();
+
+    const info = await provider.connection.getAccountInfo(delegatePda);
+    assert.isNull(info, "委托账户应该已关闭");
+  });
+});
+
+describe("token-vault: 接收者白名单", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const receiver = Keypair.generate().publicKey;
+  const vaultName = "allowlist-vault";
+
+  let vaultPda: PublicKey;
+  let allowlistPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [allowlistPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allowlist"), vaultPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+
+  it("📋 开启白名单并添加接收者", async () => {
+    await program.methods
+      .enableAllowlist(new anchor.BN(3600))
+      .accounts({ vault: vaultPda, allowlist: allowlistPda, authority })
+      .rpc();
+    await program.methods
+      .addReceiver(receiver)
+      .accounts({ vault: vaultPda, allowlist: allowlistPda, authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.isTrue(vault.receiverAllowlist);
+
+    const allowlist = await program.account.receiverAllowlist.fetch(allowlistPda);
+    assert.equal(allowlist.receivers.length, 1);
+    assert.ok(allowlist.receivers[0].receiver.equals(receiver));
+  });
+
+  it("❌ 延迟期内的接收者无法收款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({ vault: vaultPda, authority, receiver, allowlist: allowlistPda })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "ReceiverNotAllowed");
+    }
+  });
+
+  it("❌ 不传白名单账户时无法提款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(0.01 * LAMPORTS_PER_SOL))
+        .accounts({ vault: vaultPda, authority, receiver, allowlist: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "ReceiverNotAllowed");
+    }
+  });
+
+  it("📋 未生效的接收者立即移除", async () => {
+    await program.methods
+      .removeReceiver(receiver)
+      .accounts({ vault: vaultPda, allowlist: allowlistPda, authority })
+      .rpc();
+
+    const allowlist = await program.account.receiverAllowlist.fetch(allowlistPda);
+    assert.equal(allowlist.receivers.length, 0);
+  });
+
+  it("🔒 关闭金库时白名单账户一起关闭", async () => {
+    try {
+      await program.methods
+        .closeVault(null)
+        .accounts({ vault: vaultPda, authority, destination: null, allowlist: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "AllowlistAccountRequired");
+    }
+
+    await program.methods
+      .closeVault(null)
+      .accounts({ vault: vaultPda, authority, destination: null, allowlist: allowlistPda })
+      .rpc();
+
+    const allowlistInfo = await provider.connection.getAccountInfo(allowlistPda);
+    assert.isNull(allowlistInfo);
+  });
+});
+
+describe("token-vault: 紧急暂停", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const guardian = Keypair.generate();
+  const vaultName = "guarded-vault";
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ vault: vaultPda, authority, currentGuardian: null })
+      .rpc();
+  });
+
+  it("⏸️ 守护者暂停金库", async () => {
+    await program.methods
+      .pause()
+      .accounts({ vault: vaultPda, guardian: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.isTrue(vault.paused);
+  });
+
+  it("❌ 暂停期间无法存款", async () => {
+    try {
+      await program.methods
+        .deposit(new anchor.BN(0.01 * LAMPORTS_PER_SOL), null)
+        .accounts({ vault: vaultPda, depositor: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "VaultPaused");
+    }
+  });
+
+  it("❌ 所有者不能单独移除守护者", async () => {
+    try {
+      await program.methods
+        .setGuardian(null)
+        .accounts({ vault: vaultPda, authority, currentGuardian: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "VaultPaused");
+    }
+  });
+
+  it("▶️ 所有者和守护者共同解除暂停", async () => {
+    await program.methods
+      .unpause()
+      .accounts({ vault: vaultPda, authority, guardian: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.isFalse(vault.paused);
+
+    await program.methods
+      .deposit(new anchor.BN(0.01 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+});
+
+describe("token-vault: 事件", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const receiver = Keypair.generate().publicKey;
+  const vaultName = "events-vault";
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("📣 存款发出 Deposited 事件", async () => {
+    const amount = 0.1 * LAMPORTS_PER_SOL;
+    let event: any = null;
+    const listener = program.addEventListener("deposited", (e) => {
+      event = e;
+    });
+
+    await program.methods
+      .deposit(new anchor.BN(amount), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+
+    assert.isNotNull(event, "应该收到 Deposited 事件");
+    assert.ok(event.vault.equals(vaultPda));
+    assert.ok(event.depositor.equals(authority));
+    assert.equal(event.amount.toNumber(), amount);
+    assert.equal(event.totalDeposits.toNumber(), amount);
+    assert.isNull(event.reference);
+    assert.equal(
+      event.balance.toNumber(),
+      await provider.connection.getBalance(vaultPda)
+    );
+  });
+
+  it("📣 提款发出 Withdrawn 事件", async () => {
+    const amount = 0.01 * LAMPORTS_PER_SOL;
+    let event: any = null;
+    const listener = program.addEventListener("withdrawn", (e) => {
+      event = e;
+    });
+
+    await program.methods
+      .withdraw(new anchor.BN(amount))
+      .accounts({ vault: vaultPda, authority, receiver })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+
+    assert.isNotNull(event, "应该收到 Withdrawn 事件");
+    assert.ok(event.withdrawer.equals(authority));
+    assert.ok(event.receiver.equals(receiver));
+    assert.equal(event.amount.toNumber(), amount);
+    assert.equal(event.fee.toNumber(), 0);
+    assert.equal(event.totalWithdrawals.toNumber(), amount);
+  });
+});
+
+describe("token-vault: 支付流", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const recipient = Keypair.generate();
+  const vaultName = "payroll-vault";
+  const rate = 1000;
+
+  let vaultPda: PublicKey;
+  let streamPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [streamPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("stream"),
+        vaultPda.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(0.1 * LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    // 收款人需要 SOL 支付交易费
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(recipient.publicKey, LAMPORTS_PER_SOL)
+    );
+  });
+
+  it("🌊 创建支付流", async () => {
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createStream(
+        new anchor.BN(0),
+        recipient.publicKey,
+        new anchor.BN(rate),
+        new anchor.BN(now - 10),
+        new anchor.BN(now + 3600)
+      )
+      .accounts({ vault: vaultPda, stream: streamPda, authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.streamCount.toNumber(), 1);
+
+    const stream = await program.account.paymentStream.fetch(streamPda);
+    assert.ok(stream.recipient.equals(recipient.publicKey));
+    assert.equal(stream.claimed.toNumber(), 0);
+  });
+
+  it("💵 收款人领取已累积的金额", async () => {
+    await program.methods
+      .claimStream()
+      .accounts({ vault: vaultPda, stream: streamPda, recipient: recipient.publicKey })
+      .signers([recipient])
+      .rpc();
+
+    const stream = await program.account.paymentStream.fetch(streamPda);
+    assert.isAtLeast(stream.claimed.toNumber(), 10 * rate);
+  });
+
+  it("🛑 取消支付流并结算", async () => {
+    await program.methods
+      .cancelStream()
+      .accounts({
+        vault: vaultPda,
+        stream: streamPda,
+        recipient: recipient.publicKey,
+        authority,
+      })
+      .rpc();
+
+    const info = await provider.connection.getAccountInfo(streamPda);
+    assert.isNull(info, "支付流账户应该已关闭");
+  });
+});
+
+describe("token-vault: 版本迁移", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "versioned-vault";
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("📦 新金库直接使用当前版本", async () => {
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.version, 7);
+  });
+
+  it("❌ 当前版本的金库不能再次迁移", async () => {
+    try {
+      await program.methods
+        .migrateVault()
+        .accounts({ vault: vaultPda, payer: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "AlreadyMigrated");
+    }
+  });
+});
+
+describe("token-vault: 原生质押", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "staking-vault";
+  // 测试只覆盖委托之前的校验，不需要真实的投票账户
+  const voteAccount = Keypair.generate().publicKey;
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+  });
+
+  it("🥩 新金库没有质押", async () => {
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.stakedLamports.toNumber(), 0);
+  });
+
+  it("❌ 金额不足以支付质押账户租金时无法质押", async () => {
+    try {
+      await program.methods
+        .stakeIdle(new anchor.BN(1000))
+        .accounts({ vault: vaultPda, voteAccount, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidAmount");
+    }
+  });
+
+  it("❌ 没有质押时无法解除或取回", async () => {
+    try {
+      await program.methods
+        .deactivateStake()
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NoActiveStake");
+    }
+
+    try {
+      await program.methods
+        .withdrawStake()
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NoActiveStake");
+    }
+  });
+});
+
+describe("token-vault: 关闭保护", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "guarded-close";
+  const delegate = Keypair.generate();
+  const destination = Keypair.generate().publicKey;
+
+  let vaultPda: PublicKey;
+  let delegatePda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [delegatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("delegate"), vaultPda.toBuffer(), delegate.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL / 2), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    await program.methods
+      .grantDelegate(new anchor.BN(1000), null)
+      .accounts({
+        vault: vaultPda,
+        delegateAccount: delegatePda,
+        delegate: delegate.publicKey,
+        authority,
+      })
+      .rpc();
+  });
+
+  it("❌ 还有委托时无法关闭", async () => {
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.openDelegates, 1);
+
+    try {
+      await program.methods
+        .closeVault(null)
+        .accounts({ vault: vaultPda, authority, destination: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "LinkedAccountsOpen");
+    }
+  });
+
+  it("❌ require_empty 模式下余额超过阈值时无法关闭", async () => {
+    await program.methods
+      .revokeDelegate()
+      .accounts({ vault: vaultPda, delegateAccount: delegatePda, authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .closeVault(new anchor.BN(0))
+        .accounts({ vault: vaultPda, authority, destination: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "VaultNotEmpty");
+    }
+  });
+
+  it("🔒 余额转给指定的 destination", async () => {
+    const vaultBalance = await provider.connection.getBalance(vaultPda);
+
+    await program.methods
+      .closeVault(new anchor.BN(LAMPORTS_PER_SOL))
+      .accounts({ vault: vaultPda, authority, destination })
+      .rpc();
+
+    const destinationBalance = await provider.connection.getBalance(destination);
+    assert.equal(destinationBalance, vaultBalance);
+  });
+});
+
+describe("token-vault: 操作历史", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "history-vault";
+  const receiver = Keypair.generate().publicKey;
+
+  let vaultPda: PublicKey;
+  let historyPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+    [historyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("history"), vaultPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .enableHistory()
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("💰 不传历史账户仍可以存款（不写入历史）", async () => {
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), null)
+      .accounts({ vault: vaultPda, depositor: authority, history: null })
+      .rpc();
+
+    const history = await program.account.vaultHistory.fetch(historyPda);
+    assert.equal(history.total.toNumber(), 0);
+  });
+
+  it("❌ 开启后不传历史账户无法提款", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(LAMPORTS_PER_SOL / 20))
+        .accounts({ vault: vaultPda, authority, receiver, history: null })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "HistoryAccountRequired");
+    }
+  });
+
+  it("📜 存款和提款按顺序写入历史", async () => {
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), null)
+      .accounts({ vault: vaultPda, depositor: authority, history: historyPda })
+      .rpc();
+
+    await program.methods
+      .withdraw(new anchor.BN(LAMPORTS_PER_SOL / 20))
+      .accounts({ vault: vaultPda, authority, receiver, history: historyPda })
+      .rpc();
+
+    const history = await program.account.vaultHistory.fetch(historyPda);
+    assert.equal(history.total.toNumber(), 2);
+    assert.equal(history.entries.length, 2);
+    assert.deepEqual(history.entries[0].kind, { deposit: {} });
+    assert.equal(history.entries[0].amount.toNumber(), LAMPORTS_PER_SOL / 10);
+    assert.deepEqual(history.entries[1].kind, { withdraw: {} });
+    assert.equal(history.entries[1].actor.toBase58(), authority.toBase58());
+  });
+
+  it("🔒 关闭金库时历史账户一起关闭", async () => {
+    await program.methods
+      .closeVault(null)
+      .accounts({ vault: vaultPda, authority, destination: null, history: historyPda })
+      .rpc();
+
+    const historyInfo = await provider.connection.getAccountInfo(historyPda);
+    assert.isNull(historyInfo);
+  });
+});
+
+describe("token-vault: 全部 / 按比例提款", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "sweep-vault";
+  const receiver = Keypair.generate().publicKey;
+
+  let vaultPda: PublicKey;
+  let rentExempt: number;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    const vaultInfo = await provider.connection.getAccountInfo(vaultPda);
+    rentExempt = await provider.connection.getMinimumBalanceForRentExemption(
+      vaultInfo!.data.length
+    );
+  });
+
+  it("❌ 比例超过 10000 bps 时无法提款", async () => {
+    try {
+      await program.methods
+        .withdrawBps(10_001)
+        .accounts({ vault: vaultPda, authority, receiver })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidBps");
+    }
+  });
+
+  it("➗ 按比例提款返回实际金额", async () => {
+    const available = (await provider.connection.getBalance(vaultPda)) - rentExempt;
+    const expected = Math.floor((available * 2500) / 10000);
+
+    const tx = await program.methods
+      .withdrawBps(2500)
+      .accounts({ vault: vaultPda, authority, receiver })
+      .rpc({ commitment: "confirmed" });
+    assert.equal(await provider.connection.getBalance(receiver), expected);
+
+    // 返回值（u64 小端）保存在交易元数据的 returnData 中
+    const txInfo = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnData = Buffer.from(txInfo!.meta!.returnData!.data[0], "base64");
+    assert.equal(Number(returnData.readBigUInt64LE(0)), expected);
+  });
+
+  it("🧹 提取全部可用余额，只保留租金", async () => {
+    await program.methods
+      .withdrawAll()
+      .accounts({ vault: vaultPda, authority, receiver })
+      .rpc();
+
+    assert.equal(await provider.connection.getBalance(vaultPda), rentExempt);
+  });
+
+  it("❌ 没有可用余额时无法再次提取", async () => {
+    try {
+      await program.methods
+        .withdrawAll()
+        .accounts({ vault: vaultPda, authority, receiver })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidAmount");
+    }
+  });
+});
+
+describe("token-vault: 存款参考号", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "invoice-vault";
+
+  // 链下订单号，右侧补 0 到 32 字节
+  const reference = Buffer.alloc(32);
+  reference.write("INV-2024-0001");
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("📣 参考号写入 Deposited 事件", async () => {
+    let event: any = null;
+    const listener = program.addEventListener("deposited", (e) => {
+      event = e;
+    });
+
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), [...reference])
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+
+    assert.isNotNull(event, "应该收到 Deposited 事件");
+    assert.deepEqual(Buffer.from(event.reference), reference);
+  });
+
+  it("❌ 全为 0 的参考号无效", async () => {
+    try {
+      await program.methods
+        .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), new Array(32).fill(0))
+        .accounts({ vault: vaultPda, depositor: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidReference");
+    }
+  });
+
+  it("❌ 开启要求后，不带参考号的存款被拒绝", async () => {
+    await program.methods
+      .setRequireReference(true)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), null)
+        .accounts({ vault: vaultPda, depositor: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "ReferenceRequired");
+    }
+
+    // 带参考号的存款仍然可以
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL / 10), [...reference])
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.isTrue(vault.requireReference);
+    assert.equal(vault.totalDeposits.toNumber(), (2 * LAMPORTS_PER_SOL) / 10);
+  });
+});
+
+describe("token-vault: 存款限制", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "capped-vault";
+
+  const minDeposit = LAMPORTS_PER_SOL / 100;
+  const maxBalance = LAMPORTS_PER_SOL;
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("❌ 上限不能小于最低存款金额", async () => {
+    try {
+      await program.methods
+        .setDepositLimits(new anchor.BN(minDeposit), new anchor.BN(minDeposit - 1))
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidAmount");
+    }
+  });
+
+  it("🚧 设置存款限制", async () => {
+    await program.methods
+      .setDepositLimits(new anchor.BN(minDeposit), new anchor.BN(maxBalance))
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.minDeposit.toNumber(), minDeposit);
+    assert.equal(vault.maxBalance!.toNumber(), maxBalance);
+  });
+
+  it("❌ 低于最低金额的存款被拒绝", async () => {
+    try {
+      await program.methods
+        .deposit(new anchor.BN(minDeposit - 1), null)
+        .accounts({ vault: vaultPda, depositor: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "DepositTooSmall");
+    }
+  });
+
+  it("❌ 达到上限后拒绝存款", async () => {
+    // 正好存到上限
+    await program.methods
+      .deposit(new anchor.BN(maxBalance), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .deposit(new anchor.BN(minDeposit), null)
+        .accounts({ vault: vaultPda, depositor: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "MaxBalanceExceeded");
+    }
+  });
+
+  it("🔓 取消上限后可以继续存款", async () => {
+    await program.methods
+      .setDepositLimits(new anchor.BN(0), null)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(1), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.totalDeposits.toNumber(), maxBalance + 1);
+  });
+});
+
+describe("token-vault: 继承", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "heir-vault";
+  const beneficiary = Keypair.generate();
+  const stranger = Keypair.generate();
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+  });
+
+  it("❌ 不活跃期限必须大于 0", async () => {
+    try {
+      await program.methods
+        .setBeneficiary(beneficiary.publicKey, new anchor.BN(0))
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidInactivityPeriod");
+    }
+  });
+
+  it("❌ 所有者仍然活跃时不能领取", async () => {
+    await program.methods
+      .setBeneficiary(beneficiary.publicKey, new anchor.BN(3600))
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.ok(vault.beneficiary!.equals(beneficiary.publicKey));
+    assert.equal(vault.inactivityPeriod.toNumber(), 3600);
+    assert.isAbove(vault.lastHeartbeat.toNumber(), 0);
+
+    try {
+      await program.methods
+        .claimInheritance()
+        .accounts({ vault: vaultPda, beneficiary: beneficiary.publicKey })
+        .signers([beneficiary])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "OwnerStillActive");
+    }
+  });
+
+  it("💓 心跳刷新最后活跃时间", async () => {
+    const before = (await program.account.vault.fetch(vaultPda)).lastHeartbeat.toNumber();
+    await new Promise((resolve) => setTimeout(resolve, 1500));
+
+    await program.methods
+      .heartbeat()
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.isAtLeast(vault.lastHeartbeat.toNumber(), before);
+  });
+
+  it("❌ 只有受益人可以领取", async () => {
+    try {
+      await program.methods
+        .claimInheritance()
+        .accounts({ vault: vaultPda, beneficiary: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NotBeneficiary");
+    }
+  });
+
+  it("🪦 超过不活跃期限后受益人成为所有者", async () => {
+    await program.methods
+      .setBeneficiary(beneficiary.publicKey, new anchor.BN(1))
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await program.methods
+      .claimInheritance()
+      .accounts({ vault: vaultPda, beneficiary: beneficiary.publicKey })
+      .signers([beneficiary])
+      .rpc();
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.ok(vault.authority.equals(beneficiary.publicKey));
+    assert.ok(vault.originalAuthority.equals(authority));
+    assert.isNull(vault.beneficiary);
+  });
+
+  it("❌ 原所有者不能再操作金库", async () => {
+    try {
+      await program.methods
+        .heartbeat()
+        .accounts({ vault: vaultPda, authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "has_one");
+    }
+  });
+});
+
+describe("token-vault: 金库登记表", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "registry-vault";
+
+  // 同一个钱包在其他测试中也创建了金库，这里只检查本测试的金库
+  const [registryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), authority.toBuffer()],
+    program.programId
+  );
+  const [vaultPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+    program.programId
+  );
+
+  it("📇 创建金库时登记并扩容", async () => {
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    const registry = await program.account.vaultRegistry.fetch(registryPda);
+    assert.ok(registry.owner.equals(authority));
+    assert.ok(registry.vaults.some((v) => v.equals(vaultPda)));
+
+    const info = await provider.connection.getAccountInfo(registryPda);
+    assert.equal(info!.data.length, 45 + 32 * registry.vaults.length);
+  });
+
+  it("🗑️ 关闭金库时移除并缩容", async () => {
+    const countBefore = (await program.account.vaultRegistry.fetch(registryPda)).vaults.length;
+
+    await program.methods
+      .closeVault(null)
+      .accounts({ vault: vaultPda, authority, destination: null, registry: registryPda })
+      .rpc();
+
+    const registry = await program.account.vaultRegistry.fetch(registryPda);
+    assert.equal(registry.vaults.length, countBefore - 1);
+    assert.isFalse(registry.vaults.some((v) => v.equals(vaultPda)));
+
+    const info = await provider.connection.getAccountInfo(registryPda);
+    assert.equal(info!.data.length, 45 + 32 * registry.vaults.length);
+    assert.equal(
+      info!.lamports,
+      await provider.connection.getMinimumBalanceForRentExemption(info!.data.length)
+    );
+  });
+});
+
+describe("token-vault: 提款手续费", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.tokenVault as Program<TokenVault>;
+  const authority = provider.wallet.publicKey;
+  const vaultName = "fee-vault";
+  const treasury = Keypair.generate().publicKey;
+  const receiver = Keypair.generate().publicKey;
+  const stranger = Keypair.generate();
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  // anchor test 部署时，升级权限就是测试钱包
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  let vaultPda: PublicKey;
+
+  before(async () => {
+    [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), Buffer.from(vaultName)],
+      program.programId
+    );
+
+    await program.methods
+      .initialize(vaultName)
+      .accounts({ vault: vaultPda, authority })
+      .rpc();
+
+    await program.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: authority })
+      .rpc();
+
+    const sig = await provider.connection.requestAirdrop(stranger.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(sig);
+
+    // 协议金库必须已经达到租金豁免最低余额
+    const treasurySig = await provider.connection.requestAirdrop(treasury, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(treasurySig);
+  });
+
+  it("❌ 只有升级权限可以创建全局配置", async () => {
+    try {
+      await program.methods
+        .initConfig(100)
+        .accounts({ treasury, admin: stranger.publicKey, programData })
+        .signers([stranger])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "NotUpgradeAuthority");
+    }
+  });
+
+  it("❌ 手续费不能超过上限", async () => {
+    try {
+      await program.methods
+        .initConfig(1_001)
+        .accounts({ treasury, admin: authority, programData })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "FeeTooHigh");
+    }
+  });
+
+  it("❌ 协议金库必须达到租金豁免最低余额", async () => {
+    try {
+      await program.methods
+        .initConfig(100)
+        .accounts({ treasury: Keypair.generate().publicKey, admin: authority, programData })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidTreasury");
+    }
+  });
+
+  it("⚙️ 升级权限创建全局配置", async () => {
+    await program.methods
+      .initConfig(100)
+      .accounts({ treasury, admin: authority, programData })
+      .rpc();
+
+    const config = await program.account.vaultConfig.fetch(configPda);
+    assert.ok(config.admin.equals(authority));
+    assert.ok(config.treasury.equals(treasury));
+    assert.equal(config.withdrawFeeBps, 100);
+  });
+
+  it("❌ 收取手续费时必须传入协议金库", async () => {
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(LAMPORTS_PER_SOL / 10))
+        .accounts({ vault: vaultPda, authority, receiver })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "TreasuryMismatch");
+    }
+  });
+
+  it("💸 提款时手续费转给协议金库", async () => {
+    const amount = LAMPORTS_PER_SOL / 10;
+    const fee = amount / 100; // 100 bps
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    let event: any = null;
+    const listener = program.addEventListener("withdrawn", (e) => {
+      event = e;
+    });
+
+    await program.methods
+      .withdraw(new anchor.BN(amount))
+      .accounts({ vault: vaultPda, authority, receiver, treasury })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+
+    assert.equal(await provider.connection.getBalance(receiver), amount - fee);
+    assert.equal(await provider.connection.getBalance(treasury), treasuryBefore + fee);
+
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.totalWithdrawals.toNumber(), amount);
+
+    assert.isNotNull(event, "应该收到 Withdrawn 事件");
+    assert.equal(event.amount.toNumber(), amount - fee);
+    assert.equal(event.fee.toNumber(), fee);
+  });
+
+  it("❌ 只有管理员可以修改配置", async () => {
+    try {
+      await program.methods
+        .setWithdrawFee(0)
+        .accounts({ admin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "has_one");
+    }
+  });
+
+  it("⚙️ 管理员修改协议金库和手续费", async () => {
+    const newTreasury = Keypair.generate().publicKey;
+    try {
+      await program.methods
+        .setTreasury()
+        .accounts({ treasury: newTreasury, admin: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidTreasury");
+    }
+
+    const sig = await provider.connection.requestAirdrop(newTreasury, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(sig);
+    await program.methods
+      .setTreasury()
+      .accounts({ treasury: newTreasury, admin: authority })
+      .rpc();
+
+    try {
+      await program.methods
+        .setWithdrawFee(1_001)
+        .accounts({ admin: authority })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "FeeTooHigh");
+    }
+
+    // 恢复为 0，其他测试的提款不收取手续费
+    await program.methods
+      .setWithdrawFee(0)
+      .accounts({ admin: authority })
+      .rpc();
+
+    const config = await program.account.vaultConfig.fetch(configPda);
+    assert.ok(config.treasury.equals(newTreasury));
+    assert.equal(config.withdrawFeeBps, 0);
+  });
+});
+
 // ============================================================================
 // 总结
 // ============================================================================
@@ -331,10 +2341,31 @@ describe("token-vault", () => {
 // ✅ 初始化金库
 // ✅ 存款功能（单次和多次）
 // ✅ 提款功能
-// ✅ 转移所有权
+// ✅ 两步转移所有权（提议、接受、取消；默认禁用一步转移）
 // ✅ 权限验证（非所有者无法提款）
 // ✅ 余额验证（余额不足时无法提款）
 // ✅ 查询金库状态
 // ✅ 关闭金库
+// ✅ SPL 代币开通、存款、提款（按 mint 统计）
+// ✅ 时间锁（锁定期内拒绝提款、只能收紧）
+// ✅ 多签提款（阈值未达到时拒绝执行）
+// ✅ 存款回执与存款人退款
+// ✅ 提款限额（超额拒绝、放宽延迟生效）
+// ✅ 委托提款（额度、撤销）
+// ✅ 接收者白名单（延迟生效、未生效时拒绝提款）
+// ✅ 紧急暂停（暂停期间拒绝存款，所有者 + 守护者解除）
+// ✅ 事件（Deposited / Withdrawn 字段）
+// ✅ 支付流（领取、取消时结算）
+// ✅ 版本迁移（新金库为当前版本，不能重复迁移）
+// ✅ 金库名称校验（拒绝空名称和非规范名称）
+// ✅ 原生质押（租金不足时拒绝，没有质押时无法解除）
+// ✅ 关闭保护（关联账户、require_empty 阈值、指定 destination）
+// ✅ 操作历史（必须传入历史账户，按顺序记录存款和提款）
+// ✅ 全部 / 按比例提款（返回值、越界比例、只保留租金）
+// ✅ 存款参考号（写入事件、拒绝全 0、按配置强制要求）
+// ✅ 存款限制（最低存款金额、余额上限、取消上限）
+// ✅ 继承（期限校验、心跳、仅受益人、超期后接管）
+// ✅ 金库登记表（创建时登记、关闭时移除并缩容）
+// ✅ 提款手续费（仅升级权限可创建配置、费率上限、协议金库租金豁免、转给协议金库、管理员修改）
 //
 // ============================================================================
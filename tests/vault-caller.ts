@@ -0,0 +1,105 @@
+// ============================================================================
+// Vault Caller 测试：通过 CPI 使用由其他程序 PDA 所有的金库
+// ============================================================================
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { TokenVault } from "../target/types/token_vault";
+import { VaultCaller } from "../target/types/vault_caller";
+import { PublicKey, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { assert } from "chai";
+
+describe("vault-caller", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const vaultProgram = anchor.workspace.tokenVault as Program<TokenVault>;
+  const program = anchor.workspace.vaultCaller as Program<VaultCaller>;
+  const owner = provider.wallet.publicKey;
+  const vaultName = "cpi-vault";
+
+  // treasury PDA 属于 vault-caller，作为金库所有者
+  const [treasury] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury"), owner.toBuffer()],
+    program.programId
+  );
+  const [vaultPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), treasury.toBuffer(), Buffer.from(vaultName)],
+    vaultProgram.programId
+  );
+  const [registryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("registry"), treasury.toBuffer()],
+    vaultProgram.programId
+  );
+
+  it("🏦 通过 CPI 创建金库，所有者是 treasury PDA", async () => {
+    await program.methods
+      .createVault(vaultName)
+      .accounts({ vault: vaultPda, registry: registryPda, owner })
+      .rpc();
+
+    const vault = await vaultProgram.account.vault.fetch(vaultPda);
+    assert.ok(vault.authority.equals(treasury));
+    assert.ok(vault.originalAuthority.equals(treasury));
+    assert.equal(vault.vaultName, vaultName);
+
+    const registry = await vaultProgram.account.vaultRegistry.fetch(registryPda);
+    assert.ok(registry.owner.equals(treasury));
+    assert.ok(registry.vaults[0].equals(vaultPda));
+  });
+
+  it("💰 任何人都可以直接向该金库存款", async () => {
+    await vaultProgram.methods
+      .deposit(new anchor.BN(LAMPORTS_PER_SOL), null)
+      .accounts({ vault: vaultPda, depositor: owner })
+      .rpc();
+
+    const vault = await vaultProgram.account.vault.fetch(vaultPda);
+    assert.equal(vault.totalDeposits.toNumber(), LAMPORTS_PER_SOL);
+  });
+
+  it("❌ 钱包不能绕过 vault-caller 直接提款", async () => {
+    try {
+      await vaultProgram.methods
+        .withdraw(new anchor.BN(1000))
+        .accounts({ vault: vaultPda, authority: owner, receiver: owner })
+        .rpc();
+      assert.fail("应该抛出错误");
+    } catch (error: any) {
+      assert.include(error.message, "has_one");
+    }
+  });
+
+  it("💸 treasury PDA 通过 invoke_signed 签名提款", async () => {
+    const amount = LAMPORTS_PER_SOL / 4;
+    await program.methods
+      .withdraw(new anchor.BN(amount))
+      .accounts({ vault: vaultPda, owner, allowlist: null, history: null })
+      .rpc();
+
+    const vault = await vaultProgram.account.vault.fetch(vaultPda);
+    assert.equal(vault.totalWithdrawals.toNumber(), amount);
+  });
+
+  it("🧹 withdraw_all 把 CPI 返回值传给调用方", async () => {
+    const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(vaultPda))!.data.length
+    );
+    const expected = (await provider.connection.getBalance(vaultPda)) - rentExempt;
+
+    const tx = await program.methods
+      .withdrawAll()
+      .accounts({ vault: vaultPda, owner, allowlist: null, history: null })
+      .rpc({ commitment: "confirmed" });
+
+    const txInfo = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnData = txInfo!.meta!.returnData!;
+    assert.equal(returnData.programId, program.programId.toBase58());
+    const withdrawn = Buffer.from(returnData.data[0], "base64").readBigUInt64LE(0);
+    assert.equal(Number(withdrawn), expected);
+    assert.equal(await provider.connection.getBalance(vaultPda), rentExempt);
+  });
+});